@@ -0,0 +1,22 @@
+CREATE TABLE guild_settings (
+    guild_id INTEGER PRIMARY KEY NOT NULL,
+    prefix TEXT
+);
+
+CREATE TABLE user_preferences (
+    user_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
+
+CREATE TABLE command_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT NOT NULL,
+    user_id INTEGER NOT NULL,
+    guild_id INTEGER,
+    used_at INTEGER NOT NULL
+);
+
+CREATE INDEX command_usage_command ON command_usage (command);
+CREATE INDEX command_usage_user_id ON command_usage (user_id);
@@ -0,0 +1,10 @@
+CREATE TABLE favourites (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    saved_at INTEGER NOT NULL,
+    UNIQUE (user_id, url)
+);
+
+CREATE INDEX favourites_user_id ON favourites (user_id);
@@ -0,0 +1 @@
+ALTER TABLE guild_settings ADD COLUMN shared_buttons INTEGER NOT NULL DEFAULT 1;
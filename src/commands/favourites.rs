@@ -0,0 +1,24 @@
+// Copyright 2023 EagleOnGitHub
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    commands::subcommands_favourites::{list, random, remove},
+    Context, Error,
+};
+
+/// Images you saved with the ⭐ button
+#[poise::command(slash_command, prefix_command, subcommands("list", "random", "remove"))]
+pub async fn favourites(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
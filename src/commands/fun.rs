@@ -13,11 +13,16 @@
 // limitations under the License.
 
 use crate::{
-    subcommands_fun::{ascii, uwuifier},
+    subcommands_fun::{ascii, ascii_prefix, text, uwuifier},
     Context, Error,
 };
 
-#[poise::command(slash_command, subcommands("ascii", "uwuifier"))]
+/// Text toys
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommands("ascii_prefix", "ascii", "uwuifier", "text")
+)]
 pub async fn fun(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
@@ -0,0 +1,634 @@
+// Copyright 2023 EagleOnGitHub
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    error::error_embed,
+    providers::{ImageProvider, ImageResult},
+    Context, Data, Error,
+};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ChannelId, CreateComponents, CreateEmbed,
+    InteractionResponseType, Message, MessageComponentInteraction, MessageId, UserId,
+};
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// How long the buttons on an image embed keep working.
+const SESSION_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+const HISTORY_LIMIT: usize = 20;
+/// Extra fetches made when an image was already shown in the channel lately.
+const DUPLICATE_RETRIES: usize = 3;
+const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+const BUTTON_PREFIX: &str = "gallery";
+/// What the New button is called in the `[cooldowns]` config.
+const REROLL_COOLDOWN: &str = "reroll";
+pub const MAX_FAVOURITES: i64 = 500;
+
+/// Images fetched during one reroll session, oldest first, and which of them
+/// the embed is showing. The oldest image is dropped past `HISTORY_LIMIT`.
+#[derive(Default)]
+struct History {
+    images: VecDeque<ImageResult>,
+    position: usize,
+}
+
+/// What the embed shows of a history, copied out so no lock is held while
+/// talking to Discord.
+struct Page {
+    image: ImageResult,
+    position: usize,
+    len: usize,
+}
+
+impl History {
+    fn new(first: ImageResult) -> Self {
+        Self {
+            images: VecDeque::from([first]),
+            position: 0,
+        }
+    }
+
+    /// Rebuilds a one-image history from the embed a button was clicked on,
+    /// for sessions that didn't survive a restart.
+    fn from_message(message: &Message) -> Self {
+        let embed = match message.embeds.first() {
+            Some(embed) => embed,
+            None => return Self::default(),
+        };
+        match &embed.image {
+            Some(image) => Self::new(ImageResult {
+                url: image.url.clone(),
+                fields: embed
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.value.clone()))
+                    .collect(),
+            }),
+            None => Self::default(),
+        }
+    }
+
+    /// Adds a freshly fetched image at the end and jumps to it.
+    fn push(&mut self, image: ImageResult) {
+        self.images.push_back(image);
+        if self.images.len() > HISTORY_LIMIT {
+            self.images.pop_front();
+        }
+        self.position = self.images.len() - 1;
+    }
+
+    fn previous(&mut self) {
+        self.position = self.position.saturating_sub(1);
+    }
+
+    fn next(&mut self) {
+        if self.position + 1 < self.images.len() {
+            self.position += 1;
+        }
+    }
+
+    fn page(&self) -> Option<Page> {
+        Some(Page {
+            image: self.images.get(self.position)?.clone(),
+            position: self.position,
+            len: self.images.len(),
+        })
+    }
+}
+
+impl Page {
+    fn has_previous(&self) -> bool {
+        self.position > 0
+    }
+
+    fn has_next(&self) -> bool {
+        self.position + 1 < self.len
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Previous,
+    New,
+    Next,
+    Save,
+}
+
+impl Action {
+    fn as_str(self) -> &'static str {
+        match self {
+            Action::Previous => "prev",
+            Action::New => "new",
+            Action::Next => "next",
+            Action::Save => "save",
+        }
+    }
+
+    fn parse(action: &str) -> Option<Self> {
+        match action {
+            "prev" => Some(Action::Previous),
+            "new" => Some(Action::New),
+            "next" => Some(Action::Next),
+            "save" => Some(Action::Save),
+            _ => None,
+        }
+    }
+}
+
+/// Everything a gallery button needs is in its `custom_id`, laid out as
+/// `gallery:<action>:<session>:<owner>:<provider>`, so the buttons work
+/// without any state from the command that sent them. The provider goes last
+/// because breed-specific provider names contain `:` themselves.
+struct ButtonId {
+    action: Action,
+    session: u64,
+    owner: UserId,
+    provider: String,
+}
+
+impl ButtonId {
+    fn parse(custom_id: &str) -> Option<Self> {
+        let mut parts = custom_id.splitn(5, ':');
+        if parts.next()? != BUTTON_PREFIX {
+            return None;
+        }
+        Some(Self {
+            action: Action::parse(parts.next()?)?,
+            session: parts.next()?.parse().ok()?,
+            owner: UserId(parts.next()?.parse().ok()?),
+            provider: parts.next()?.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ButtonId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}:{}",
+            BUTTON_PREFIX,
+            self.action.as_str(),
+            self.session,
+            self.owner,
+            self.provider
+        )
+    }
+}
+
+/// Session ids are the snowflake of the command invocation, so their age
+/// is known even after a restart.
+fn session_age(session: u64) -> Duration {
+    let created_ms = (session >> 22) + DISCORD_EPOCH_MS;
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis() as u64)
+        .unwrap_or_default();
+    Duration::from_millis(now_ms.saturating_sub(created_ms))
+}
+
+struct Session {
+    history: History,
+    provider: Arc<dyn ImageProvider>,
+    owner: UserId,
+    channel_id: ChannelId,
+    message_id: MessageId,
+}
+
+/// Reroll histories of the image embeds whose buttons still work, keyed by
+/// session id.
+#[derive(Default)]
+pub struct Sessions {
+    sessions: Mutex<HashMap<u64, Session>>,
+}
+
+impl Sessions {
+    fn start(
+        &self,
+        session: u64,
+        provider: Arc<dyn ImageProvider>,
+        owner: UserId,
+        history: History,
+        message: &Message,
+    ) {
+        self.sessions.lock().unwrap().insert(
+            session,
+            Session {
+                history,
+                provider,
+                owner,
+                channel_id: message.channel_id,
+                message_id: message.id,
+            },
+        );
+    }
+
+    /// Applies `update` to the session's history, restoring the session from
+    /// the clicked message first if it isn't known.
+    fn update(
+        &self,
+        id: &ButtonId,
+        provider: &Arc<dyn ImageProvider>,
+        message: &Message,
+        update: impl FnOnce(&mut History),
+    ) -> Option<Page> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = match sessions.entry(id.session) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Session {
+                history: History::from_message(message),
+                provider: provider.clone(),
+                owner: id.owner,
+                channel_id: message.channel_id,
+                message_id: message.id,
+            }),
+        };
+        update(&mut session.history);
+        session.history.page()
+    }
+
+    fn remove(&self, session: u64) {
+        self.sessions.lock().unwrap().remove(&session);
+    }
+
+    fn take_expired(&self) -> Vec<(u64, Session)> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let expired = sessions
+            .keys()
+            .copied()
+            .filter(|session| session_age(*session) > SESSION_TTL)
+            .collect::<Vec<_>>();
+        expired
+            .into_iter()
+            .filter_map(|session| sessions.remove_entry(&session))
+            .collect()
+    }
+}
+
+fn image_embed<'a>(
+    e: &'a mut CreateEmbed,
+    provider: &dyn ImageProvider,
+    page: &Page,
+) -> &'a mut CreateEmbed {
+    e.title(format!("Random {}", provider.title()));
+    e.image(&page.image.url);
+    for (name, value) in &page.image.fields {
+        e.field(name, value, true);
+    }
+    if page.len > 1 {
+        e.footer(|f| f.text(format!("Image {}/{}", page.position + 1, page.len)));
+    }
+    e
+}
+
+/// `page` is `None` once the session expired, which disables every button.
+fn reroll_buttons<'a>(
+    c: &'a mut CreateComponents,
+    provider: &dyn ImageProvider,
+    session: u64,
+    owner: UserId,
+    page: Option<&Page>,
+) -> &'a mut CreateComponents {
+    let id = |action| {
+        ButtonId {
+            action,
+            session,
+            owner,
+            provider: provider.name().to_string(),
+        }
+        .to_string()
+    };
+    c.create_action_row(|ar| {
+        ar.create_button(|b| {
+            b.style(ButtonStyle::Secondary)
+                .label("◀ Previous")
+                .custom_id(id(Action::Previous))
+                .disabled(!page.is_some_and(Page::has_previous))
+        })
+        .create_button(|b| {
+            b.style(ButtonStyle::Primary)
+                .label(format!("New {}", provider.title()))
+                .custom_id(id(Action::New))
+                .disabled(page.is_none())
+        })
+        .create_button(|b| {
+            b.style(ButtonStyle::Secondary)
+                .label("Next ▶")
+                .custom_id(id(Action::Next))
+                .disabled(!page.is_some_and(Page::has_next))
+        })
+        .create_button(|b| {
+            b.style(ButtonStyle::Secondary)
+                .label("⭐ Save")
+                .custom_id(id(Action::Save))
+                .disabled(page.is_none())
+        })
+    })
+}
+
+async fn reply_ephemeral(
+    ctx: &serenity::Context,
+    mci: &MessageComponentInteraction,
+    content: impl ToString,
+) -> Result<(), Error> {
+    mci.create_interaction_response(ctx, |ir| {
+        ir.kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|d| d.ephemeral(true).content(content))
+    })
+    .await?;
+    Ok(())
+}
+
+/// Saves the image shown on the clicked message to the clicking user's
+/// favourites and tells them, privately, how it went.
+async fn save_favourite(
+    ctx: &serenity::Context,
+    data: &Data,
+    mci: &MessageComponentInteraction,
+    provider: &dyn ImageProvider,
+) -> Result<(), Error> {
+    let page = match History::from_message(&mci.message).page() {
+        Some(page) => page,
+        None => return reply_ephemeral(ctx, mci, "There's no image here to save.").await,
+    };
+    let favourites = data.storage.favourites();
+    let user_id = mci.user.id.0;
+    let message = if favourites.count(user_id).await? >= MAX_FAVOURITES {
+        format!(
+            "You already have {} favourites, remove some with `/favourites remove` first.",
+            MAX_FAVOURITES
+        )
+    } else if favourites
+        .add(user_id, &page.image.url, provider.name())
+        .await?
+    {
+        String::from("Saved to your favourites! See them with `/favourites list`.")
+    } else {
+        String::from("That one is already in your favourites.")
+    };
+    reply_ephemeral(ctx, mci, message).await
+}
+
+/// A prefetched image the channel hasn't seen lately, if there is one.
+fn take_unseen(
+    data: &Data,
+    channel: ChannelId,
+    provider: &Arc<dyn ImageProvider>,
+) -> Option<ImageResult> {
+    let image = data.prefetch.take(provider)?;
+    if data.recent.contains(channel, &image.url) {
+        return None;
+    }
+    data.recent.insert(channel, &image.url);
+    Some(image)
+}
+
+/// Fetches an image the channel hasn't seen lately. Some providers only have
+/// a small pool of pictures, so once the retries run out a repeat is shown
+/// rather than nothing.
+async fn fetch_unseen(
+    data: &Data,
+    channel: ChannelId,
+    provider: &Arc<dyn ImageProvider>,
+) -> Result<ImageResult, Error> {
+    let mut image = data.prefetch.fetch(provider).await?;
+    for _ in 0..DUPLICATE_RETRIES {
+        if !data.recent.contains(channel, &image.url) {
+            break;
+        }
+        match data.prefetch.fetch(provider).await {
+            Ok(next) => image = next,
+            Err(err) => {
+                tracing::debug!("Retrying a repeat from {} failed: {}", provider.name(), err);
+                break;
+            }
+        }
+    }
+    data.recent.insert(channel, &image.url);
+    Ok(image)
+}
+
+/// Rerolls with a fresh fetch when nothing was prefetched. Fetching can take
+/// longer than Discord waits for a response, so the click is deferred and
+/// the message edited through the interaction afterwards.
+async fn reroll_slowly(
+    ctx: &serenity::Context,
+    data: &Data,
+    mci: &MessageComponentInteraction,
+    id: &ButtonId,
+    provider: Arc<dyn ImageProvider>,
+) -> Result<(), Error> {
+    mci.create_interaction_response(ctx, |ir| {
+        ir.kind(InteractionResponseType::DeferredUpdateMessage)
+    })
+    .await?;
+    let image = match fetch_unseen(data, mci.channel_id, &provider).await {
+        Ok(image) => image,
+        Err(err) => {
+            tracing::warn!("Reroll from {} failed: {}", provider.name(), err);
+            mci.create_followup_message(ctx, |f| f.ephemeral(true).embed(|e| error_embed(e, &err)))
+                .await?;
+            return Ok(());
+        }
+    };
+    let page = data
+        .galleries
+        .update(id, &provider, &mci.message, |history| history.push(image))
+        .ok_or("history is empty after a push")?;
+    mci.edit_original_interaction_response(ctx, |r| {
+        r.embed(|e| image_embed(e, provider.as_ref(), &page))
+            .components(|c| reroll_buttons(c, provider.as_ref(), id.session, id.owner, Some(&page)))
+    })
+    .await?;
+    Ok(())
+}
+
+/// Sends an image from the named provider with a "New X" button that rerolls
+/// it, Previous/Next buttons to go back through earlier rerolls and a
+/// "⭐ Save" button that adds the shown image to the clicker's favourites.
+/// The buttons are handled by `handle_component`, not by this command, so
+/// they keep working after it returns. Prefix invocations get the same
+/// buttons on the bot's reply, so `fl.animals cat` behaves like `/animals cat`.
+/// Images the channel was shown lately are skipped, see `fetch_unseen`.
+pub async fn gallery(ctx: Context<'_>, provider: &str) -> Result<(), Error> {
+    let provider = ctx
+        .data()
+        .providers
+        .get(provider)
+        .ok_or_else(|| format!("unknown image provider {}", provider))?;
+    let session = ctx.id();
+    let owner = ctx.author().id;
+    // Failover and duplicate retries can take longer than Discord waits for
+    // the first response.
+    ctx.defer().await?;
+    let history = History::new(fetch_unseen(ctx.data(), ctx.channel_id(), &provider).await?);
+    let page = history.page().ok_or("new history is empty")?;
+
+    let reply = ctx
+        .send(|m| {
+            m.content("")
+                .embed(|e| image_embed(e, provider.as_ref(), &page))
+                .components(|c| reroll_buttons(c, provider.as_ref(), session, owner, Some(&page)))
+        })
+        .await?;
+    let message = reply.message().await?;
+    ctx.data()
+        .galleries
+        .start(session, provider, owner, history, &message);
+
+    Ok(())
+}
+
+/// Handles clicks on gallery buttons from the framework's event handler.
+/// Anyone can use them unless the guild turned that off with
+/// `/config buttons`; saving to your own favourites is always allowed.
+/// New has a cooldown like a command does, under `REROLL_COOLDOWN`.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: &Data,
+    mci: &MessageComponentInteraction,
+) -> Result<(), Error> {
+    let id = match ButtonId::parse(&mci.data.custom_id) {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let provider = match data.providers.get(&id.provider) {
+        Some(provider) => provider,
+        None => {
+            return reply_ephemeral(ctx, mci, "That image source isn't available anymore.").await
+        }
+    };
+
+    if session_age(id.session) > SESSION_TTL {
+        data.galleries.remove(id.session);
+        mci.create_interaction_response(ctx, |ir| {
+            ir.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.components(|c| {
+                        reroll_buttons(c, provider.as_ref(), id.session, id.owner, None)
+                    })
+                })
+        })
+        .await?;
+        mci.create_followup_message(ctx, |f| {
+            f.ephemeral(true)
+                .content("These buttons expired, run the command again for new ones.")
+        })
+        .await?;
+        return Ok(());
+    }
+
+    if id.action == Action::Save {
+        return save_favourite(ctx, data, mci, provider.as_ref()).await;
+    }
+    if let Some(guild_id) = mci.guild_id {
+        if mci.user.id != id.owner && !data.storage.guilds().get(guild_id.0).await?.shared_buttons {
+            return reply_ephemeral(
+                ctx,
+                mci,
+                format!("Only <@{}> can use these buttons in this server.", id.owner),
+            )
+            .await;
+        }
+    }
+
+    if id.action == Action::New {
+        let cooldown =
+            data.cooldowns
+                .check(REROLL_COOLDOWN, mci.user.id, mci.channel_id, mci.guild_id);
+        if let Err(err) = cooldown {
+            let err: Error = Box::new(err);
+            mci.create_interaction_response(ctx, |ir| {
+                ir.kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|d| {
+                        d.ephemeral(true).embed(|e| error_embed(e, &err))
+                    })
+            })
+            .await?;
+            return Ok(());
+        }
+    }
+
+    let page = match id.action {
+        Action::Previous | Action::Next => {
+            let page = data
+                .galleries
+                .update(&id, &provider, &mci.message, |history| {
+                    if id.action == Action::Previous {
+                        history.previous();
+                    } else {
+                        history.next();
+                    }
+                });
+            match page {
+                Some(page) => page,
+                None => {
+                    mci.create_interaction_response(ctx, |ir| {
+                        ir.kind(InteractionResponseType::DeferredUpdateMessage)
+                    })
+                    .await?;
+                    return Ok(());
+                }
+            }
+        }
+        // A prefetched image can be shown right away, without deferring.
+        _ => match take_unseen(data, mci.channel_id, &provider) {
+            Some(image) => data
+                .galleries
+                .update(&id, &provider, &mci.message, |history| history.push(image))
+                .ok_or("history is empty after a push")?,
+            None => return reroll_slowly(ctx, data, mci, &id, provider).await,
+        },
+    };
+
+    mci.create_interaction_response(ctx, |ir| {
+        ir.kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|d| {
+                d.embed(|e| image_embed(e, provider.as_ref(), &page))
+                    .components(|c| {
+                        reroll_buttons(c, provider.as_ref(), id.session, id.owner, Some(&page))
+                    })
+            })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Disables the buttons of sessions past `SESSION_TTL` so they don't sit
+/// there looking clickable. Sessions from before a restart aren't known here
+/// and get disabled when someone clicks them instead.
+pub async fn expire_sessions(http: Arc<serenity::Http>, sessions: Arc<Sessions>) {
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        for (id, session) in sessions.take_expired() {
+            let result = session
+                .channel_id
+                .edit_message(&http, session.message_id, |m| {
+                    m.components(|c| {
+                        reroll_buttons(c, session.provider.as_ref(), id, session.owner, None)
+                    })
+                })
+                .await;
+            if let Err(err) = result {
+                tracing::debug!("Couldn't disable expired gallery {}: {}", id, err);
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2023 EagleOnGitHub
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{commands::paginate::paginate, error::BotError, Context, Data, Error};
+use poise::serenity_prelude::CreateEmbed;
+
+type Command = poise::Command<Data, Error>;
+
+fn visible(command: &Command) -> bool {
+    !command.hide_in_help
+        && (command.slash_action.is_some()
+            || command.prefix_action.is_some()
+            || !command.subcommands.is_empty())
+}
+
+fn flatten<'a>(commands: &'a [Command], out: &mut Vec<&'a Command>) {
+    for command in commands.iter().filter(|command| visible(command)) {
+        out.push(command);
+        flatten(&command.subcommands, out);
+    }
+}
+
+fn usage(prefix: &str, command: &Command) -> String {
+    let mut usage = format!("{}{}", prefix, command.qualified_name);
+    for parameter in &command.parameters {
+        if parameter.required {
+            usage += &format!(" <{}>", parameter.name);
+        } else {
+            usage += &format!(" [{}]", parameter.name);
+        }
+    }
+    usage
+}
+
+fn command_page<'a>(
+    e: &'a mut CreateEmbed,
+    prefix: &str,
+    command: &Command,
+) -> &'a mut CreateEmbed {
+    e.title(usage(prefix, command)).description(
+        command
+            .description
+            .as_deref()
+            .unwrap_or("No description available."),
+    );
+    if let Some(help_text) = command.help_text {
+        e.field("Details", help_text(), false);
+    }
+    if !command.parameters.is_empty() {
+        let parameters = command
+            .parameters
+            .iter()
+            .map(|parameter| {
+                format!(
+                    "`{}`{} - {}",
+                    parameter.name,
+                    if parameter.required {
+                        ""
+                    } else {
+                        " (optional)"
+                    },
+                    parameter.description.as_deref().unwrap_or("no description")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        e.field("Parameters", parameters, false);
+    }
+    let subcommands = command
+        .subcommands
+        .iter()
+        .filter(|subcommand| visible(subcommand))
+        .map(|subcommand| {
+            format!(
+                "`{}` - {}",
+                usage(prefix, subcommand),
+                subcommand
+                    .description
+                    .as_deref()
+                    .unwrap_or("no description")
+            )
+        })
+        .collect::<Vec<_>>();
+    if !subcommands.is_empty() {
+        e.field("Subcommands", subcommands.join("\n"), false);
+    }
+    e
+}
+
+async fn autocomplete_command(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    flatten(&ctx.framework().options().commands, &mut commands);
+    let partial = partial.to_lowercase();
+    commands
+        .into_iter()
+        .map(|command| command.qualified_name.clone())
+        .filter(|name| name.to_lowercase().contains(&partial))
+        .take(25)
+        .collect()
+}
+
+/// Show what the bot can do
+///
+/// Without a command, pages through every command group. With one, shows
+/// its parameters and subcommands.
+/// Example: `/help animals cat`
+#[poise::command(slash_command, prefix_command)]
+pub async fn help(
+    ctx: Context<'_>,
+    #[description = "The command to show help for"]
+    #[autocomplete = "autocomplete_command"]
+    #[rest]
+    command: Option<String>,
+) -> Result<(), Error> {
+    let prefix = ctx.prefix().to_string();
+    let mut all = Vec::new();
+    flatten(&ctx.framework().options().commands, &mut all);
+
+    let pages: Vec<&Command> = match command {
+        Some(query) => {
+            let query = query.trim().trim_start_matches(prefix.as_str()).trim();
+            let found = all
+                .iter()
+                .find(|command| command.qualified_name.eq_ignore_ascii_case(query))
+                .or_else(|| {
+                    all.iter()
+                        .find(|command| command.name.eq_ignore_ascii_case(query))
+                })
+                .ok_or_else(|| {
+                    BotError::InvalidInput(format!("There is no command called `{}`.", query))
+                })?;
+            vec![*found]
+        }
+        None => ctx
+            .framework()
+            .options()
+            .commands
+            .iter()
+            .filter(|command| visible(command))
+            .collect(),
+    };
+
+    paginate(ctx, pages.len(), |page, e| {
+        command_page(e, &prefix, pages[page]);
+    })
+    .await
+}
@@ -20,8 +20,10 @@ use crate::{
     Context, Error,
 };
 
+/// Random animal pictures
 #[poise::command(
     slash_command,
+    prefix_command,
     subcommands(
         "cat", "dog", "fox", "shiba", "bird", "cat2", "cat3", "httpcat", "httpdog", "duck", "dog2"
     )
@@ -30,8 +32,10 @@ pub async fn animals(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Random anime pictures
 #[poise::command(
     slash_command,
+    prefix_command,
     subcommands("neko", "neko2", "neko3", "kitsune", "okami")
 )]
 pub async fn anime(_ctx: Context<'_>) -> Result<(), Error> {
@@ -12,9 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{commands::subcommands_info::avatar, Context, Error};
+use crate::{
+    commands::subcommands_info::{avatar, profile},
+    Context, Error,
+};
 
-#[poise::command(slash_command, subcommands("avatar"))]
+/// Information about users
+#[poise::command(slash_command, prefix_command, subcommands("avatar", "profile"))]
 pub async fn userinfo(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
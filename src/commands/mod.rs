@@ -12,9 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod favourites;
 pub mod fun;
+pub mod gallery;
+pub mod help;
 pub mod images;
 pub mod info;
+pub mod paginate;
+pub mod settings;
+pub mod subcommands_favourites;
 pub mod subcommands_fun;
 pub mod subcommands_images;
 pub mod subcommands_info;
+pub mod subcommands_server;
+pub mod subcommands_settings;
+pub mod subcommands_text;
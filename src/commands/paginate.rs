@@ -0,0 +1,135 @@
+// Copyright 2023 EagleOnGitHub
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Context, Error};
+use poise::serenity_prelude::{
+    ButtonStyle, CollectComponentInteraction, CreateComponents, CreateEmbed,
+    InteractionResponseType,
+};
+use std::time::Duration;
+
+const PAGINATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// `expired` disables both buttons once nobody is listening for clicks.
+fn page_buttons(
+    c: &mut CreateComponents,
+    id: u64,
+    page: usize,
+    pages: usize,
+    expired: bool,
+) -> &mut CreateComponents {
+    c.create_action_row(|ar| {
+        ar.create_button(|b| {
+            b.style(ButtonStyle::Secondary)
+                .label("Previous")
+                .custom_id(format!("{}prev", id))
+                .disabled(expired || page == 0)
+        })
+        .create_button(|b| {
+            b.style(ButtonStyle::Secondary)
+                .label("Next")
+                .custom_id(format!("{}next", id))
+                .disabled(expired || page + 1 == pages)
+        })
+    })
+}
+
+fn page_embed<'a, F>(
+    e: &'a mut CreateEmbed,
+    render: &F,
+    page: usize,
+    pages: usize,
+) -> &'a mut CreateEmbed
+where
+    F: Fn(usize, &mut CreateEmbed),
+{
+    render(page, e);
+    if pages > 1 {
+        e.footer(|f| f.text(format!("Page {}/{}", page + 1, pages)));
+    }
+    e
+}
+
+/// Shows `pages` embeds one at a time with Previous/Next buttons. `render`
+/// fills in the embed for a zero-based page index.
+pub async fn paginate<F>(ctx: Context<'_>, pages: usize, render: F) -> Result<(), Error>
+where
+    F: Fn(usize, &mut CreateEmbed) + Send + Sync,
+{
+    let id = ctx.id();
+    let mut page = 0;
+
+    let reply = ctx
+        .send(|m| {
+            m.embed(|e| page_embed(e, &render, page, pages));
+            if pages > 1 {
+                m.components(|c| page_buttons(c, id, page, pages, false));
+            }
+            m
+        })
+        .await?;
+    if pages <= 1 {
+        return Ok(());
+    }
+
+    let mut last_click = None;
+    while let Some(mci) = CollectComponentInteraction::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(PAGINATION_TIMEOUT)
+        .filter(move |mci| mci.data.custom_id.starts_with(&id.to_string()))
+        .await
+    {
+        if mci.data.custom_id.ends_with("next") {
+            page = (page + 1).min(pages - 1);
+        } else {
+            page = page.saturating_sub(1);
+        }
+
+        mci.create_interaction_response(ctx, |ir| {
+            ir.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|e| page_embed(e, &render, page, pages))
+                        .components(|c| page_buttons(c, id, page, pages, false))
+                })
+        })
+        .await?;
+        last_click = Some(mci);
+    }
+
+    // The command's own token only lasts 15 minutes, the last click's is
+    // still fresh.
+    let expired = match last_click {
+        Some(mci) => mci
+            .edit_original_interaction_response(ctx, |r| {
+                r.embed(|e| page_embed(e, &render, page, pages))
+                    .components(|c| page_buttons(c, id, page, pages, true))
+            })
+            .await
+            .map(|_| ()),
+        None => {
+            reply
+                .edit(ctx, |m| {
+                    m.embed(|e| page_embed(e, &render, page, pages))
+                        .components(|c| page_buttons(c, id, page, pages, true))
+                })
+                .await
+        }
+    };
+    if let Err(err) = expired {
+        tracing::warn!("Failed to disable page buttons: {}", err);
+    }
+
+    Ok(())
+}
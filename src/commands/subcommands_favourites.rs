@@ -0,0 +1,165 @@
+// Copyright 2023 EagleOnGitHub
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{commands::paginate::paginate, error::BotError, storage::Favourite, Context, Error};
+use poise::serenity_prelude::CreateEmbed;
+
+const NO_FAVOURITES: &str =
+    "You haven't saved any images yet. Press ⭐ Save on an image to keep it here.";
+
+/// The provider's title if it is still registered, otherwise its raw name.
+fn provider_title(ctx: Context<'_>, favourite: &Favourite) -> String {
+    ctx.data()
+        .providers
+        .get(&favourite.provider)
+        .map(|provider| provider.title().to_string())
+        .unwrap_or_else(|| favourite.provider.clone())
+}
+
+fn favourite_embed<'a>(
+    e: &'a mut CreateEmbed,
+    favourite: &Favourite,
+    title: &str,
+) -> &'a mut CreateEmbed {
+    e.title(title)
+        .description(format!(
+            "ID `{}`, saved <t:{}:R>",
+            favourite.id, favourite.saved_at
+        ))
+        .image(&favourite.url)
+}
+
+async fn say_no_favourites(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.send(|m| m.ephemeral(true).content(NO_FAVOURITES))
+        .await?;
+    Ok(())
+}
+
+async fn autocomplete_favourite(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<i64>> {
+    let favourites = match ctx
+        .data()
+        .storage
+        .favourites()
+        .list(ctx.author().id.0)
+        .await
+    {
+        Ok(favourites) => favourites,
+        Err(err) => {
+            tracing::warn!("Couldn't list favourites for autocomplete: {}", err);
+            return Vec::new();
+        }
+    };
+    let partial = partial.to_lowercase();
+    favourites
+        .into_iter()
+        .map(|favourite| {
+            let name = format!(
+                "#{} {} - {}",
+                favourite.id,
+                provider_title(ctx, &favourite),
+                favourite.url
+            );
+            (name, favourite.id)
+        })
+        .filter(|(name, _)| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|(name, id)| poise::AutocompleteChoice {
+            name: name.chars().take(100).collect(),
+            value: id,
+        })
+        .collect()
+}
+
+/// Page through the images you saved
+///
+/// Newest first.
+/// Example: `/favourites list`
+#[poise::command(slash_command, prefix_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let favourites = ctx
+        .data()
+        .storage
+        .favourites()
+        .list(ctx.author().id.0)
+        .await?;
+    if favourites.is_empty() {
+        return say_no_favourites(ctx).await;
+    }
+    let titles = favourites
+        .iter()
+        .map(|favourite| provider_title(ctx, favourite))
+        .collect::<Vec<_>>();
+
+    paginate(ctx, favourites.len(), |page, e| {
+        favourite_embed(e, &favourites[page], &titles[page]);
+    })
+    .await
+}
+
+/// Show one of your saved images at random
+///
+/// Example: `/favourites random`
+#[poise::command(slash_command, prefix_command)]
+pub async fn random(ctx: Context<'_>) -> Result<(), Error> {
+    let favourite = match ctx
+        .data()
+        .storage
+        .favourites()
+        .random(ctx.author().id.0)
+        .await?
+    {
+        Some(favourite) => favourite,
+        None => return say_no_favourites(ctx).await,
+    };
+    let title = provider_title(ctx, &favourite);
+
+    ctx.send(|m| m.embed(|e| favourite_embed(e, &favourite, &title)))
+        .await?;
+
+    Ok(())
+}
+
+/// Remove an image from your favourites
+///
+/// The ID is shown on each image in `/favourites list`.
+/// Example: `/favourites remove id:12`
+#[poise::command(slash_command, prefix_command)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "The favourite to remove"]
+    #[autocomplete = "autocomplete_favourite"]
+    id: i64,
+) -> Result<(), Error> {
+    let removed = ctx
+        .data()
+        .storage
+        .favourites()
+        .remove(ctx.author().id.0, id)
+        .await?;
+    if !removed {
+        return Err(
+            BotError::InvalidInput(format!("You don't have a favourite with ID {}.", id)).into(),
+        );
+    }
+    ctx.send(|m| {
+        m.ephemeral(true)
+            .content(format!("Removed favourite {}.", id))
+    })
+    .await?;
+
+    Ok(())
+}
@@ -12,30 +12,156 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{Context, Error};
-use figlet_rs::FIGfont;
+use crate::{
+    commands::subcommands_text::{
+        emojify, leet, mock, owo, owo_prefix, reverse, say_transformed, smallcaps, upsidedown,
+        vaporwave, zalgo, zalgo_prefix,
+    },
+    error::BotError,
+    figlet::{self, Font},
+    prefix::take_option,
+    transform::{Transform, MESSAGE_LIMIT},
+    Context, Error,
+};
+use poise::serenity_prelude::{AttachmentType, Message};
+use std::borrow::Cow;
 
+const DEFAULT_WIDTH: u16 = 80;
+const MIN_WIDTH: u16 = 20;
+const MAX_WIDTH: u16 = 200;
+
+async fn autocomplete_font(
+    _ctx: Context<'_>,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<String>> {
+    figlet::search(partial)
+        .map(|font| poise::AutocompleteChoice {
+            name: format!("{} - {}", font.name, font.description),
+            value: font.name.to_string(),
+        })
+        .collect()
+}
+
+/// Turn text into ASCII art
+///
+/// Long text is wrapped to `width` columns. Art too long for a message is sent as a text file.
+///
+/// Example: `/fun ascii text:floppa font:blocks` or `fl.fun ascii floppa font=blocks width=40`
 #[poise::command(slash_command)]
 pub async fn ascii(
     ctx: Context<'_>,
     #[description = "The string you want to turn into ASCII"] text: String,
+    #[description = "Font to draw with (default: standard)"]
+    #[autocomplete = "autocomplete_font"]
+    font: Option<Font>,
+    #[description = "Maximum width of the art in columns (default: 80)"]
+    #[min = 20]
+    #[max = 200]
+    width: Option<u16>,
+) -> Result<(), Error> {
+    send_ascii(
+        ctx,
+        font.unwrap_or_default(),
+        width.unwrap_or(DEFAULT_WIDTH),
+        &text,
+    )
+    .await
+}
+
+/// The prefix form of `ascii`, where `font=` and `width=` can go anywhere in
+/// the text. It has to come before `ascii` in the subcommands, since prefix
+/// commands are looked up by the first matching name or alias.
+#[poise::command(prefix_command, aliases("ascii"), hide_in_help)]
+pub async fn ascii_prefix(
+    ctx: Context<'_>,
+    #[description = "The string you want to turn into ASCII"]
+    #[rest]
+    text: String,
 ) -> Result<(), Error> {
-    let standard_font = FIGfont::standard().expect("figlet font fail");
-    let figure = standard_font
-        .convert(&text)
-        .expect("figlet convertion fail");
-    let send = format!("```{}```", figure.to_string());
-    ctx.say(send).await?;
+    let mut text = text;
+    let font = match take_option(&mut text, "font") {
+        Some(font) => font.parse()?,
+        None => Font::default(),
+    };
+    let width = match take_option(&mut text, "width") {
+        Some(width) => width
+            .parse()
+            .ok()
+            .filter(|width| (MIN_WIDTH..=MAX_WIDTH).contains(width))
+            .ok_or_else(|| {
+                BotError::InvalidInput(format!(
+                    "The width has to be a number from {} to {}.",
+                    MIN_WIDTH, MAX_WIDTH
+                ))
+            })?,
+        None => DEFAULT_WIDTH,
+    };
+    send_ascii(ctx, font, width, &text).await
+}
+
+async fn send_ascii(ctx: Context<'_>, font: Font, width: u16, text: &str) -> Result<(), Error> {
+    let figfont = font.load()?;
+    let width = width.clamp(MIN_WIDTH, MAX_WIDTH);
+    let art = figlet::render(&figfont, text, width.into())?;
+
+    let block = format!("```\n{}\n```", art);
+    if block.chars().count() <= MESSAGE_LIMIT {
+        ctx.say(block).await?;
+    } else {
+        ctx.send(|m| {
+            m.content("That's too big for a message, here it is as a file:")
+                .attachment(AttachmentType::Bytes {
+                    data: Cow::Owned(art.into_bytes()),
+                    filename: String::from("ascii.txt"),
+                })
+        })
+        .await?;
+    }
 
     Ok(())
 }
 
-#[poise::command(slash_command)]
+/// UwU-ify some text
+///
+/// Example: `/fun uwuifier text:hello there`
+#[poise::command(slash_command, prefix_command)]
 pub async fn uwuifier(
     ctx: Context<'_>,
-    #[description = "The text you want to uwu-ify"] text: String,
+    #[description = "The text you want to uwu-ify"]
+    #[rest]
+    text: String,
 ) -> Result<(), Error> {
-    ctx.say(uwuifier::uwuify_str_sse(&text)).await?;
+    say_transformed(ctx, Transform::Uwuify, &text).await
+}
+
+#[poise::command(context_menu_command = "ASCII art")]
+pub async fn ascii_message(ctx: Context<'_>, msg: Message) -> Result<(), Error> {
+    send_ascii(ctx, Font::default(), DEFAULT_WIDTH, &msg.content).await
+}
+
+#[poise::command(context_menu_command = "UwU-ify")]
+pub async fn uwuify_message(ctx: Context<'_>, msg: Message) -> Result<(), Error> {
+    say_transformed(ctx, Transform::Uwuify, &msg.content).await
+}
 
+/// Transform text in silly ways
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommands(
+        "mock",
+        "leet",
+        "zalgo_prefix",
+        "zalgo",
+        "reverse",
+        "upsidedown",
+        "vaporwave",
+        "smallcaps",
+        "emojify",
+        "owo_prefix",
+        "owo"
+    )
+)]
+pub async fn text(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
@@ -12,1034 +12,220 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{
-    secrets::{CAT_API_KEY, DOG_API_KEY},
-    Context, Error,
-};
-use serde::Deserialize;
-use substring::Substring;
+use crate::{commands::gallery::gallery, error::BotError, http_status, Context, Error};
 
-#[derive(Deserialize)]
-struct Breeds {
-    weight: Option<Vec<String>>,
-    id: Option<String>,
-    name: Option<String>,
-    temperament: Option<String>,
-    origin: Option<String>,
-    country_codes: Option<String>,
-    country_code: Option<String>,
-    life_span: Option<String>,
-    wikipedia_url: Option<String>,
-}
-
-#[derive(Deserialize)]
-struct AnimalAPIResponse {
-    id: String,
-    width: i16,
-    height: i16,
-    url: String,
-    breeds: Option<Vec<Breeds>>,
-}
-
-#[derive(Deserialize)]
-struct RandomFox {
-    image: String,
-    link: String,
-}
-
-#[derive(Deserialize)]
-struct AWSRandomCat {
-    file: String,
-}
-
-#[derive(Deserialize)]
-struct RandomDog {
-    #[serde(rename = "fileSizeBytes")]
-    file_size_bytes: i128,
-    url: String,
-}
-
-#[derive(Deserialize)]
-struct RandomDuck {
-    url: String,
-    message: String,
-}
-
-#[derive(Deserialize)]
-struct PurrbotImage {
-    error: bool,
-    link: String,
-    time: i16,
-}
-
-#[derive(Deserialize)]
-struct WaifuPics {
-    url: String,
-}
-
-enum AnimalOnlineChoices {
-    Bird,
-    Shibe,
-    Cat,
-}
-
-enum PurrbotImageChoices {
-    Kitsune,
-    Neko,
-    Okami,
-}
-
-impl PurrbotImageChoices {
-    fn as_str(&self) -> &'static str {
-        match self {
-            PurrbotImageChoices::Kitsune => "kitsune",
-            PurrbotImageChoices::Okami => "okami",
-            PurrbotImageChoices::Neko => "neko",
-        }
-    }
-}
-
-impl AnimalOnlineChoices {
-    fn as_str(&self) -> &'static str {
-        match self {
-            AnimalOnlineChoices::Bird => "birds",
-            AnimalOnlineChoices::Shibe => "shibes",
-            AnimalOnlineChoices::Cat => "cats",
-        }
-    }
-}
-
-fn request_animalapi(cat: bool) -> Vec<AnimalAPIResponse> {
-    let url: String;
-    url = match cat {
-        true => {
-            format!(
-                "https://api.thecatapi.com/v1/images/search?api_key={}",
-                CAT_API_KEY
-            )
-        }
-        false => {
-            format!(
-                "https://api.thedogapi.com/v1/images/search?api_key={}",
-                DOG_API_KEY
-            )
-        }
+async fn autocomplete_breed(
+    ctx: Context<'_>,
+    provider: &str,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<String>> {
+    let breeds = match ctx.data().providers.get(provider) {
+        Some(provider) => provider.breeds(&ctx.data().http).await.unwrap_or_default(),
+        None => Vec::new(),
     };
-    ureq::get(&url)
-        .call()
-        .expect("error in req")
-        .into_json()
-        .expect("error in json")
-}
-
-fn request_fox() -> RandomFox {
-    ureq::get("https://randomfox.ca/floof/")
-        .call()
-        .expect("error in req")
-        .into_json()
-        .expect("error in json")
+    let partial = partial.to_lowercase();
+    breeds
+        .into_iter()
+        .filter(|(name, _)| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|(name, id)| poise::AutocompleteChoice { name, value: id })
+        .collect()
 }
 
-fn request_animalonline(animal: AnimalOnlineChoices) -> String {
-    let url = format!("http://shibe.online/api/{}", animal.as_str());
-    let mut shibe = ureq::get(&url)
-        .call()
-        .expect("error in req")
-        .into_string()
-        .expect("error in string");
-    shibe = shibe.substring(2, shibe.chars().count() - 2).to_string();
-    shibe
-}
-
-fn request_awscat() -> AWSRandomCat {
-    ureq::get("https://aws.random.cat/meow")
-        .call()
-        .expect("error in req")
-        .into_json()
-        .expect("error in json")
-}
-
-fn request_randomdog() -> RandomDog {
-    ureq::get("https://random.dog/woof.json")
-        .call()
-        .expect("error in req")
-        .into_json()
-        .expect("error in json")
-}
-
-fn request_randomduck() -> RandomDuck {
-    ureq::get("https://random-d.uk/api/v2/random")
-        .call()
-        .expect("error in req")
-        .into_json()
-        .expect("error in json")
+async fn autocomplete_cat_breed(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<String>> {
+    autocomplete_breed(ctx, "thecatapi", partial).await
 }
 
-fn request_purrbot(choice: PurrbotImageChoices) -> PurrbotImage {
-    let url = format!("https://purrbot.site/api/img/sfw/{}/img", choice.as_str());
-    ureq::get(url.as_str())
-        .call()
-        .expect("error in req")
-        .into_json()
-        .expect("error in json")
+async fn autocomplete_dog_breed(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<String>> {
+    autocomplete_breed(ctx, "thedogapi", partial).await
 }
 
-fn request_waifupics(neko: bool) -> String {
-    let complete = match neko {
-        true => "neko",
-        false => "waifu",
-    };
-    let url = format!("https://api.waifu.pics/sfw/{}", complete);
-    let response: WaifuPics = ureq::get(url.as_str())
-        .call()
-        .expect("error in req")
-        .into_json()
-        .expect("error in json");
-    response.url
+async fn autocomplete_status(
+    _ctx: Context<'_>,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<u16>> {
+    http_status::search(partial)
+        .take(25)
+        .map(|status| poise::AutocompleteChoice {
+            name: format!("{} {}", status.code, status.reason),
+            value: status.code,
+        })
+        .collect()
 }
 
-// animals
-
-#[poise::command(slash_command)]
-pub async fn cat(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_animalapi(true);
-
+async fn status_image(ctx: Context<'_>, code: u16, site: &str) -> Result<(), Error> {
+    let status = http_status::lookup(code).ok_or_else(|| {
+        BotError::InvalidInput(format!("{} is not a known HTTP status code.", code))
+    })?;
     ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Cat");
-                e.image(&parsed[0].url)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Cat")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
+        m.embed(|e| {
+            e.title(format!("{} {}", status.code, status.reason))
+                .description(status.description)
+                .image(format!("{}/{}.jpg", site, status.code))
+                .footer(|f| f.text(status.reference))
+        })
     })
     .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Cat");
-                    e.image(&request_animalapi(true)[0].url)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Cat")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
     Ok(())
 }
 
-#[poise::command(slash_command)]
-pub async fn dog(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_animalapi(false);
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Dog");
-                e.image(&parsed[0].url)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Dog")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Dog");
-                    e.image(&request_animalapi(false)[0].url)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Dog")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
+/// Accepts either a breed id picked from autocomplete or a typed breed name.
+async fn breed_gallery(ctx: Context<'_>, provider: &str, breed: &str) -> Result<(), Error> {
+    ctx.defer().await?;
+    let breeds = ctx
+        .data()
+        .providers
+        .get(provider)
+        .ok_or_else(|| format!("unknown image provider {}", provider))?
+        .breeds(&ctx.data().http)
         .await?;
-    }
-
-    Ok(())
+    let (_, breed_id) = breeds
+        .iter()
+        .find(|(name, id)| id.eq_ignore_ascii_case(breed) || name.eq_ignore_ascii_case(breed))
+        .ok_or_else(|| BotError::InvalidInput(format!("I don't know a breed called {}.", breed)))?;
+    gallery(ctx, &format!("{}:{}", provider, breed_id)).await
 }
 
-#[poise::command(slash_command)]
-pub async fn fox(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_fox();
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Fox");
-                e.image(&parsed.image)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Fox")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
+// animals
 
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Fox");
-                    e.image(&request_fox().image)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Fox")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
+/// Random cat picture
+///
+/// Falls back to other cat APIs when TheCatAPI is down.
+/// Example: `/animals cat breed:Bengal`
+#[poise::command(slash_command, prefix_command)]
+pub async fn cat(
+    ctx: Context<'_>,
+    #[description = "Only show this breed"]
+    #[autocomplete = "autocomplete_cat_breed"]
+    breed: Option<String>,
+) -> Result<(), Error> {
+    match breed {
+        Some(breed) => breed_gallery(ctx, "thecatapi", &breed).await,
+        None => gallery(ctx, "cat").await,
+    }
+}
 
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
+/// Random dog picture
+///
+/// Falls back to random.dog when TheDogAPI is down.
+/// Example: `/animals dog breed:Beagle`
+#[poise::command(slash_command, prefix_command)]
+pub async fn dog(
+    ctx: Context<'_>,
+    #[description = "Only show this breed"]
+    #[autocomplete = "autocomplete_dog_breed"]
+    breed: Option<String>,
+) -> Result<(), Error> {
+    match breed {
+        Some(breed) => breed_gallery(ctx, "thedogapi", &breed).await,
+        None => gallery(ctx, "dog").await,
     }
+}
 
-    Ok(())
+/// Random fox picture from randomfox.ca
+#[poise::command(slash_command, prefix_command)]
+pub async fn fox(ctx: Context<'_>) -> Result<(), Error> {
+    gallery(ctx, "randomfox").await
 }
 
-#[poise::command(slash_command)]
+/// Random shiba picture from shibe.online
+#[poise::command(slash_command, prefix_command)]
 pub async fn shiba(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_animalonline(AnimalOnlineChoices::Shibe);
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Shiba");
-                e.image(&parsed)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Shiba")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Shiba");
-                    e.image(&request_animalonline(AnimalOnlineChoices::Shibe))
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Shiba")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "shibe-shibes").await
 }
 
-#[poise::command(slash_command)]
+/// Random bird picture from shibe.online
+#[poise::command(slash_command, prefix_command)]
 pub async fn bird(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_animalonline(AnimalOnlineChoices::Bird);
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Bird");
-                e.image(&parsed)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Bird")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Bird");
-                    e.image(&request_animalonline(AnimalOnlineChoices::Bird))
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Bird")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "shibe-birds").await
 }
 
-#[poise::command(slash_command)]
+/// Random cat picture from shibe.online
+#[poise::command(slash_command, prefix_command)]
 pub async fn cat2(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_animalonline(AnimalOnlineChoices::Cat);
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Cat");
-                e.image(&parsed)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Cat")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Cat");
-                    e.image(&request_animalonline(AnimalOnlineChoices::Cat))
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Cat")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "shibe-cats").await
 }
 
-#[poise::command(slash_command)]
+/// Random cat picture from aws.random.cat
+#[poise::command(slash_command, prefix_command)]
 pub async fn cat3(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_awscat().file;
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Cat");
-                e.image(&parsed)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Cat")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Cat");
-                    e.image(&request_awscat().file)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Cat")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "awscat").await
 }
 
-#[poise::command(slash_command)]
+/// Random duck picture from random-d.uk
+#[poise::command(slash_command, prefix_command)]
 pub async fn duck(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_randomduck();
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Duck");
-                e.image(&parsed.url)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Duck")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Duck");
-                    e.image(&request_randomduck().url)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Duck")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "randomduck").await
 }
 
-#[poise::command(slash_command)]
+/// Random dog picture from random.dog
+#[poise::command(slash_command, prefix_command)]
 pub async fn dog2(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_randomdog();
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Dog");
-                e.image(&parsed.url)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Dog")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Dog");
-                    e.image(&request_randomdog().url)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Dog")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "randomdog").await
 }
 
-#[poise::command(slash_command)]
+/// HTTP status code as a cat
+///
+/// Example: `/animals httpcat code:404`
+#[poise::command(slash_command, prefix_command)]
 pub async fn httpcat(
     ctx: Context<'_>,
-    #[description = "The HTTP code"] code: i16,
+    #[description = "The HTTP code"]
+    #[autocomplete = "autocomplete_status"]
+    code: u16,
 ) -> Result<(), Error> {
-    let send = format!("https://http.cat/{}", code);
-    ctx.say(send).await?;
-    Ok(())
+    status_image(ctx, code, "https://http.cat").await
 }
 
-#[poise::command(slash_command)]
+/// HTTP status code as a dog
+///
+/// Example: `/animals httpdog code:418`
+#[poise::command(slash_command, prefix_command)]
 pub async fn httpdog(
     ctx: Context<'_>,
-    #[description = "The HTTP code"] code: i16,
+    #[description = "The HTTP code"]
+    #[autocomplete = "autocomplete_status"]
+    code: u16,
 ) -> Result<(), Error> {
-    let send = format!("https://http.dog/{}", code);
-    ctx.say(send).await?;
-    Ok(())
+    status_image(ctx, code, "https://http.dog").await
 }
 
 // anime
 
-#[poise::command(slash_command)]
+/// Random neko picture
+///
+/// Falls back to waifu.pics and purrbot.site when nekos.best is down.
+#[poise::command(slash_command, prefix_command)]
 pub async fn neko(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = nekosbest::get(nekosbest::Category::Neko).await?;
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Neko");
-                e.image(&parsed.url)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Neko")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let response = nekosbest::get(nekosbest::Category::Neko).await?.url;
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Neko");
-                    e.image(&response)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Neko")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "neko").await
 }
 
-#[poise::command(slash_command)]
+/// Random neko picture from waifu.pics
+#[poise::command(slash_command, prefix_command)]
 pub async fn neko2(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_waifupics(true);
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Neko");
-                e.image(&parsed)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Neko")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Neko");
-                    e.image(&request_waifupics(true))
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Neko")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "waifupics-neko").await
 }
 
-#[poise::command(slash_command)]
+/// Random neko picture from purrbot.site
+#[poise::command(slash_command, prefix_command)]
 pub async fn neko3(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_purrbot(PurrbotImageChoices::Neko);
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Neko");
-                e.image(&parsed.link);
-                e.field("API Response Time", &parsed.time, true)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Neko")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let response = request_purrbot(PurrbotImageChoices::Neko);
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Neko");
-                    e.image(&response.link);
-                    e.field("API Response Time", &response.time, true)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Neko")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "purrbot-neko").await
 }
 
-#[poise::command(slash_command)]
+/// Random okami picture from purrbot.site
+#[poise::command(slash_command, prefix_command)]
 pub async fn okami(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_purrbot(PurrbotImageChoices::Okami);
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Okami");
-                e.image(&parsed.link);
-                e.field("API Response Time", &parsed.time, true)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Okami")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let response = request_purrbot(PurrbotImageChoices::Okami);
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Okami");
-                    e.image(&response.link);
-                    e.field("API Response Time", &response.time, true)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Okami")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "purrbot-okami").await
 }
 
-#[poise::command(slash_command)]
+/// Random kitsune picture from purrbot.site
+#[poise::command(slash_command, prefix_command)]
 pub async fn kitsune(ctx: Context<'_>) -> Result<(), Error> {
-    let button_uuid = ctx.id();
-    let parsed = request_purrbot(PurrbotImageChoices::Kitsune);
-
-    ctx.send(|m| {
-        m.content("")
-            .embed(|e| {
-                e.title("Random Kitsune");
-                e.image(&parsed.link);
-                e.field("API Response Time", &parsed.time, true)
-            })
-            .components(|c| {
-                c.create_action_row(|ar| {
-                    ar.create_button(|b| {
-                        b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                            .label("New Kitsune")
-                            .custom_id(button_uuid)
-                    })
-                })
-            })
-    })
-    .await?;
-
-    while let Some(mci) = poise::serenity_prelude::CollectComponentInteraction::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == button_uuid.to_string())
-        .await
-    {
-        let response = request_purrbot(PurrbotImageChoices::Kitsune);
-        let mut msg = mci.message.clone();
-        msg.edit(ctx, |m| {
-            m.content("")
-                .embed(|e| {
-                    e.title("Random Kitsune");
-                    e.image(&response.link);
-                    e.field("API Response Time", &response.time, true)
-                })
-                .components(|c| {
-                    c.create_action_row(|ar| {
-                        ar.create_button(|b| {
-                            b.style(poise::serenity_prelude::ButtonStyle::Primary)
-                                .label("New Kitsune")
-                                .custom_id(button_uuid)
-                        })
-                    })
-                })
-        })
-        .await?;
-
-        mci.create_interaction_response(ctx, |ir| {
-            ir.kind(poise::serenity_prelude::InteractionResponseType::DeferredUpdateMessage)
-        })
-        .await?;
-    }
-
-    Ok(())
+    gallery(ctx, "purrbot-kitsune").await
 }
@@ -13,18 +13,345 @@
 // limitations under the License.
 
 use crate::{Context, Error};
-use poise::serenity_prelude::User;
+use poise::serenity_prelude::{CreateEmbed, Member, Timestamp, User, UserPublicFlags};
+use std::cmp::Reverse;
 
-#[poise::command(slash_command)]
+const CDN: &str = "https://cdn.discordapp.com";
+const FIELD_LIMIT: usize = 1024;
+
+static BADGES: &[(UserPublicFlags, &str)] = &[
+    (UserPublicFlags::DISCORD_EMPLOYEE, "Discord Staff"),
+    (
+        UserPublicFlags::PARTNERED_SERVER_OWNER,
+        "Partnered Server Owner",
+    ),
+    (UserPublicFlags::HYPESQUAD_EVENTS, "HypeSquad Events"),
+    (UserPublicFlags::BUG_HUNTER_LEVEL_1, "Bug Hunter"),
+    (UserPublicFlags::BUG_HUNTER_LEVEL_2, "Bug Hunter Gold"),
+    (UserPublicFlags::HOUSE_BRAVERY, "HypeSquad Bravery"),
+    (UserPublicFlags::HOUSE_BRILLIANCE, "HypeSquad Brilliance"),
+    (UserPublicFlags::HOUSE_BALANCE, "HypeSquad Balance"),
+    (UserPublicFlags::EARLY_SUPPORTER, "Early Supporter"),
+    (UserPublicFlags::VERIFIED_BOT, "Verified Bot"),
+    (
+        UserPublicFlags::EARLY_VERIFIED_BOT_DEVELOPER,
+        "Early Verified Bot Developer",
+    ),
+    (
+        UserPublicFlags::DISCORD_CERTIFIED_MODERATOR,
+        "Discord Certified Moderator",
+    ),
+];
+
+#[derive(Clone, Copy, poise::ChoiceParameter)]
+pub enum AvatarSize {
+    #[name = "64"]
+    S64,
+    #[name = "128"]
+    S128,
+    #[name = "256"]
+    S256,
+    #[name = "512"]
+    S512,
+    #[name = "1024"]
+    S1024,
+    #[name = "2048"]
+    S2048,
+    #[name = "4096"]
+    S4096,
+}
+
+impl AvatarSize {
+    fn pixels(self) -> u16 {
+        match self {
+            AvatarSize::S64 => 64,
+            AvatarSize::S128 => 128,
+            AvatarSize::S256 => 256,
+            AvatarSize::S512 => 512,
+            AvatarSize::S1024 => 1024,
+            AvatarSize::S2048 => 2048,
+            AvatarSize::S4096 => 4096,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum ImageFormat {
+    #[name = "png"]
+    Png,
+    #[name = "webp"]
+    Webp,
+    #[name = "gif"]
+    Gif,
+    #[name = "jpg"]
+    Jpg,
+}
+
+impl ImageFormat {
+    const ALL: [ImageFormat; 4] = [
+        ImageFormat::Png,
+        ImageFormat::Webp,
+        ImageFormat::Jpg,
+        ImageFormat::Gif,
+    ];
+
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Gif => "gif",
+            ImageFormat::Jpg => "jpg",
+        }
+    }
+}
+
+/// Where an avatar lives on the CDN, so it can be rendered at any size and
+/// format instead of serenity's fixed webp/gif URLs.
+enum AvatarSource {
+    Guild {
+        path: String,
+        animated: bool,
+    },
+    User {
+        path: String,
+        animated: bool,
+    },
+    /// Default avatars only exist as png.
+    Default(String),
+}
+
+impl AvatarSource {
+    fn new(user: &User, member: Option<&Member>, server: bool) -> Self {
+        if let Some((member, hash)) = member
+            .filter(|_| server)
+            .and_then(|member| member.avatar.as_ref().map(|hash| (member, hash)))
+        {
+            return AvatarSource::Guild {
+                path: format!(
+                    "{}/guilds/{}/users/{}/avatars/{}",
+                    CDN, member.guild_id, user.id, hash
+                ),
+                animated: hash.starts_with("a_"),
+            };
+        }
+        match &user.avatar {
+            Some(hash) => AvatarSource::User {
+                path: format!("{}/avatars/{}/{}", CDN, user.id, hash),
+                animated: hash.starts_with("a_"),
+            },
+            None => {
+                // Accounts on the new username system have discriminator 0.
+                let index = if user.discriminator == 0 {
+                    (user.id.0 >> 22) % 6
+                } else {
+                    u64::from(user.discriminator % 5)
+                };
+                AvatarSource::Default(format!("{}/embed/avatars/{}.png", CDN, index))
+            }
+        }
+    }
+
+    fn formats(&self) -> Vec<ImageFormat> {
+        match self {
+            AvatarSource::Guild { animated, .. } | AvatarSource::User { animated, .. } => {
+                ImageFormat::ALL
+                    .into_iter()
+                    .filter(|format| *animated || *format != ImageFormat::Gif)
+                    .collect()
+            }
+            AvatarSource::Default(_) => vec![ImageFormat::Png],
+        }
+    }
+
+    /// Falls back to png when the requested format doesn't exist for this avatar.
+    fn url(&self, format: Option<ImageFormat>, size: u16) -> String {
+        match self {
+            AvatarSource::Guild { path, animated } | AvatarSource::User { path, animated } => {
+                let default = if *animated {
+                    ImageFormat::Gif
+                } else {
+                    ImageFormat::Png
+                };
+                let format = format
+                    .filter(|format| self.formats().contains(format))
+                    .unwrap_or(default);
+                format!("{}.{}?size={}", path, format.extension(), size)
+            }
+            AvatarSource::Default(url) => url.clone(),
+        }
+    }
+}
+
+pub fn discord_time(timestamp: Timestamp) -> String {
+    format!("<t:{0}:F> (<t:{0}:R>)", timestamp.unix_timestamp())
+}
+
+async fn guild_member(ctx: Context<'_>, user: &User) -> Option<Member> {
+    let guild_id = ctx.guild_id()?;
+    guild_id.member(ctx, user.id).await.ok()
+}
+
+/// Role mentions, highest role first when the guild is cached, cut short
+/// to fit in an embed field.
+fn role_list(ctx: Context<'_>, member: &Member) -> String {
+    let mut roles = member.roles.clone();
+    if let Some(guild) = ctx.guild() {
+        roles.sort_by_key(|id| Reverse(guild.roles.get(id).map_or(0, |role| role.position)));
+    }
+    let mut list = String::new();
+    for (shown, role) in roles.iter().enumerate() {
+        let mention = format!("<@&{}> ", role);
+        let more = format!("and {} more", roles.len() - shown);
+        if list.len() + mention.len() + more.len() > FIELD_LIMIT {
+            list += &more;
+            return list;
+        }
+        list += &mention;
+    }
+    list
+}
+
+fn profile_embed<'a>(
+    ctx: Context<'_>,
+    e: &'a mut CreateEmbed,
+    user: &User,
+    member: Option<&Member>,
+) -> &'a mut CreateEmbed {
+    e.title(user.tag())
+        .thumbnail(member.map_or_else(|| user.face(), |member| member.face()))
+        .field("ID", user.id, true)
+        .field("Bot", if user.bot { "Yes" } else { "No" }, true)
+        .field("Account created", discord_time(user.created_at()), false);
+    if let Some(member) = member {
+        if let Some(nick) = &member.nick {
+            e.field("Nickname", nick, true);
+        }
+        if let Some(joined_at) = member.joined_at {
+            e.field("Joined this server", discord_time(joined_at), false);
+        }
+        if !member.roles.is_empty() {
+            e.field(
+                format!("Roles ({})", member.roles.len()),
+                role_list(ctx, member),
+                false,
+            );
+        }
+    }
+    let badges = user
+        .public_flags
+        .map(|flags| {
+            BADGES
+                .iter()
+                .filter(|(flag, _)| flags.contains(*flag))
+                .map(|(_, name)| *name)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if !badges.is_empty() {
+        e.field("Badges", badges.join(", "), false);
+    }
+    if let Some(colour) = user.accent_colour {
+        e.field("Accent colour", format!("#{}", colour.hex()), true)
+            .colour(colour);
+    }
+    if let Some(banner) = user.banner_url() {
+        e.image(banner);
+    }
+    e
+}
+
+async fn send_avatar(
+    ctx: Context<'_>,
+    user: &User,
+    size: Option<AvatarSize>,
+    format: Option<ImageFormat>,
+    server: bool,
+) -> Result<(), Error> {
+    let member = guild_member(ctx, user).await;
+    let source = AvatarSource::new(user, member.as_ref(), server);
+    let size = size.map_or(1024, AvatarSize::pixels);
+    let links = source
+        .formats()
+        .into_iter()
+        .map(|format| {
+            format!(
+                "[{}]({})",
+                format.extension().to_uppercase(),
+                source.url(Some(format), size)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" | ");
+    let title = match &source {
+        AvatarSource::Guild { .. } => format!("{}'s server avatar", user.name),
+        _ => format!("{}'s avatar", user.name),
+    };
+
+    ctx.send(|m| {
+        m.embed(|e| {
+            e.title(title)
+                .description(links)
+                .image(source.url(format, size))
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Users in interactions don't carry their banner or accent colour, only a
+/// fresh fetch does.
+async fn send_profile(ctx: Context<'_>, user: &User) -> Result<(), Error> {
+    let user = match ctx.serenity_context().http.get_user(user.id.0).await {
+        Ok(user) => user,
+        Err(err) => {
+            tracing::warn!("Couldn't fetch full profile of {}: {}", user.id, err);
+            user.clone()
+        }
+    };
+    let member = guild_member(ctx, &user).await;
+    ctx.send(|m| m.embed(|e| profile_embed(ctx, e, &user, member.as_ref())))
+        .await?;
+
+    Ok(())
+}
+
+/// Show someone's avatar
+///
+/// Shows your own avatar when no user is given.
+/// In a server, their server avatar is shown if they have one.
+/// Example: `/userinfo avatar user:@someone size:4096 format:png`
+#[poise::command(slash_command, prefix_command)]
 pub async fn avatar(
     ctx: Context<'_>,
     #[description = "User you want to get avatar of"] user: Option<User>,
+    #[description = "Size in pixels (default: 1024)"] size: Option<AvatarSize>,
+    #[description = "Image format (default: png or gif)"] format: Option<ImageFormat>,
+    #[description = "Use their server avatar if they have one (default: yes)"] server: Option<bool>,
 ) -> Result<(), Error> {
     let u = user.as_ref().unwrap_or_else(|| ctx.author());
-    let response = u
-        .avatar_url()
-        .unwrap_or_else(|| String::from("Error getting user avatar"));
-    ctx.say(response).await?;
+    send_avatar(ctx, u, size, format, server.unwrap_or(true)).await
+}
 
-    Ok(())
+/// Show someone's profile
+///
+/// Shows your own profile when no user is given.
+/// Example: `/userinfo profile user:@someone`
+#[poise::command(slash_command, prefix_command)]
+pub async fn profile(
+    ctx: Context<'_>,
+    #[description = "User you want to see the profile of"] user: Option<User>,
+) -> Result<(), Error> {
+    let u = user.as_ref().unwrap_or_else(|| ctx.author());
+    send_profile(ctx, u).await
+}
+
+#[poise::command(context_menu_command = "Avatar")]
+pub async fn avatar_user(ctx: Context<'_>, user: User) -> Result<(), Error> {
+    send_avatar(ctx, &user, None, None, true).await
+}
+
+#[poise::command(context_menu_command = "User info")]
+pub async fn userinfo_user(ctx: Context<'_>, user: User) -> Result<(), Error> {
+    send_profile(ctx, &user).await
 }
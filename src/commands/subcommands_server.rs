@@ -0,0 +1,292 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{commands::subcommands_info::discord_time, Context, Error};
+use poise::serenity_prelude::{
+    Channel, ChannelType, GuildChannel, GuildId, Permissions, PremiumTier, Role, UserId,
+};
+
+const FIELD_LIMIT: usize = 1024;
+
+/// The parts of a guild `/serverinfo` shows, from the cache or from a fetch.
+struct ServerInfo {
+    id: GuildId,
+    name: String,
+    description: Option<String>,
+    owner_id: UserId,
+    members: u64,
+    online: Option<u64>,
+    text_channels: usize,
+    voice_channels: usize,
+    categories: usize,
+    roles: usize,
+    emojis: usize,
+    boosts: u64,
+    tier: PremiumTier,
+    features: Vec<String>,
+    icon: Option<String>,
+    banner: Option<String>,
+    splash: Option<String>,
+}
+
+impl ServerInfo {
+    fn count_channels<'a>(&mut self, channels: impl Iterator<Item = &'a GuildChannel>) {
+        for channel in channels {
+            match channel.kind {
+                ChannelType::Text | ChannelType::News => self.text_channels += 1,
+                ChannelType::Voice | ChannelType::Stage => self.voice_channels += 1,
+                ChannelType::Category => self.categories += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+async fn server_info(ctx: Context<'_>, guild_id: GuildId) -> Result<ServerInfo, Error> {
+    if let Some(guild) = ctx.guild() {
+        let mut info = ServerInfo {
+            id: guild.id,
+            name: guild.name.clone(),
+            description: guild.description.clone(),
+            owner_id: guild.owner_id,
+            members: guild.member_count,
+            online: None,
+            text_channels: 0,
+            voice_channels: 0,
+            categories: 0,
+            roles: guild.roles.len(),
+            emojis: guild.emojis.len(),
+            boosts: guild.premium_subscription_count,
+            tier: guild.premium_tier,
+            features: guild.features.clone(),
+            icon: guild.icon_url(),
+            banner: guild.banner_url(),
+            splash: guild.splash_url(),
+        };
+        info.count_channels(guild.channels.values().filter_map(|channel| match channel {
+            Channel::Guild(channel) => Some(channel),
+            _ => None,
+        }));
+        return Ok(info);
+    }
+
+    let guild = guild_id
+        .to_partial_guild_with_counts(ctx.serenity_context())
+        .await?;
+    let channels = guild_id.channels(ctx).await?;
+    let mut info = ServerInfo {
+        id: guild.id,
+        name: guild.name.clone(),
+        description: guild.description.clone(),
+        owner_id: guild.owner_id,
+        members: guild.approximate_member_count.unwrap_or_default(),
+        online: guild.approximate_presence_count,
+        text_channels: 0,
+        voice_channels: 0,
+        categories: 0,
+        roles: guild.roles.len(),
+        emojis: guild.emojis.len(),
+        boosts: guild.premium_subscription_count,
+        tier: guild.premium_tier,
+        features: guild.features.clone(),
+        icon: guild.icon_url(),
+        banner: guild.banner_url(),
+        splash: guild.splash_url(),
+    };
+    info.count_channels(channels.values());
+    Ok(info)
+}
+
+fn tier_name(tier: PremiumTier) -> &'static str {
+    match tier {
+        PremiumTier::Tier0 => "None",
+        PremiumTier::Tier1 => "Level 1",
+        PremiumTier::Tier2 => "Level 2",
+        PremiumTier::Tier3 => "Level 3",
+        _ => "Unknown",
+    }
+}
+
+/// `ANIMATED_ICON` -> `Animated Icon`
+fn feature_name(feature: &str) -> String {
+    feature
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_string() + &chars.as_str().to_lowercase(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Joins `items` with ", ", stopping with "and N more" before the embed
+/// field limit.
+fn field_list(items: &[String]) -> String {
+    let mut list = String::new();
+    for (shown, item) in items.iter().enumerate() {
+        let more = format!("and {} more", items.len() - shown);
+        if list.len() + item.len() + more.len() + 2 > FIELD_LIMIT {
+            list += &more;
+            return list;
+        }
+        if !list.is_empty() {
+            list += ", ";
+        }
+        list += item;
+    }
+    list
+}
+
+/// Show information about this server
+///
+/// Example: `/serverinfo`
+#[poise::command(slash_command, prefix_command, guild_only)]
+pub async fn serverinfo(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("serverinfo used outside a guild")?;
+    let info = server_info(ctx, guild_id).await?;
+
+    let members = match info.online {
+        Some(online) => format!("{} ({} online)", info.members, online),
+        None => info.members.to_string(),
+    };
+    let channels = format!(
+        "{} text, {} voice, {} categories",
+        info.text_channels, info.voice_channels, info.categories
+    );
+    let mut features = info
+        .features
+        .iter()
+        .map(|feature| feature_name(feature))
+        .collect::<Vec<_>>();
+    features.sort();
+    let images = [
+        ("Icon", &info.icon),
+        ("Banner", &info.banner),
+        ("Splash", &info.splash),
+    ]
+    .into_iter()
+    .filter_map(|(name, url)| url.as_ref().map(|url| format!("[{}]({})", name, url)))
+    .collect::<Vec<_>>();
+
+    ctx.send(|m| {
+        m.embed(|e| {
+            e.title(&info.name)
+                .field("ID", info.id, true)
+                .field("Owner", format!("<@{}>", info.owner_id), true)
+                .field("Members", members, true)
+                .field("Channels", channels, true)
+                .field("Roles", info.roles, true)
+                .field("Emojis", info.emojis, true)
+                .field(
+                    "Boosts",
+                    format!("{} ({})", info.boosts, tier_name(info.tier)),
+                    true,
+                )
+                .field("Created", discord_time(info.id.created_at()), false);
+            if let Some(description) = &info.description {
+                e.description(description);
+            }
+            if !features.is_empty() {
+                e.field("Features", field_list(&features), false);
+            }
+            if !images.is_empty() {
+                e.field("Images", images.join(" | "), false);
+            }
+            if let Some(icon) = &info.icon {
+                e.thumbnail(icon);
+            }
+            if let Some(banner) = &info.banner {
+                e.image(banner);
+            }
+            e
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Show information about a role
+///
+/// The member count only includes members the bot has seen.
+/// Example: `/roleinfo role:@Moderators`
+#[poise::command(slash_command, prefix_command, guild_only)]
+pub async fn roleinfo(
+    ctx: Context<'_>,
+    #[description = "The role you want to know about"] role: Role,
+) -> Result<(), Error> {
+    // Without the members intent the cache only holds members we've seen, so
+    // the count is a lower bound unless the whole member list is cached.
+    let members = ctx.guild().map(|guild| {
+        let count = guild
+            .members
+            .values()
+            .filter(|member| member.roles.contains(&role.id))
+            .count();
+        if guild.members.len() as u64 >= guild.member_count {
+            count.to_string()
+        } else {
+            format!("at least {}", count)
+        }
+    });
+    let permissions = if role.permissions.contains(Permissions::ADMINISTRATOR) {
+        String::from("Administrator (every permission)")
+    } else if role.permissions.is_empty() {
+        String::from("None")
+    } else {
+        let names = role
+            .permissions
+            .get_permission_names()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        field_list(&names)
+    };
+    let yes_no = |value: bool| if value { "Yes" } else { "No" };
+
+    ctx.send(|m| {
+        m.embed(|e| {
+            e.title(&role.name)
+                .field("ID", role.id, true)
+                .field("Position", role.position, true)
+                .field(
+                    "Colour",
+                    if role.colour.0 == 0 {
+                        String::from("Default")
+                    } else {
+                        format!("#{}", role.colour.hex())
+                    },
+                    true,
+                )
+                .field("Mentionable", yes_no(role.mentionable), true)
+                .field("Hoisted", yes_no(role.hoist), true)
+                .field("Managed", yes_no(role.managed), true)
+                .field("Created", discord_time(role.id.created_at()), false)
+                .field("Permissions", permissions, false);
+            if let Some(members) = members {
+                e.field("Members", members, true);
+            }
+            if role.colour.0 != 0 {
+                e.colour(role.colour);
+            }
+            e
+        })
+    })
+    .await?;
+
+    Ok(())
+}
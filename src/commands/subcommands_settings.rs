@@ -0,0 +1,93 @@
+// Copyright 2023 EagleOnGitHub
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{error::BotError, Context, Error};
+
+const MAX_PREFIX_LENGTH: usize = 10;
+
+/// Change the prefix for prefix commands in this server
+///
+/// Only members with Manage Server can change it.
+/// Example: `/config prefix prefix:!`
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn prefix(
+    ctx: Context<'_>,
+    #[description = "The new prefix, leave empty to go back to the default"] prefix: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("config prefix used outside a guild")?;
+    if let Some(prefix) = &prefix {
+        if prefix.chars().count() > MAX_PREFIX_LENGTH || prefix.chars().any(char::is_whitespace) {
+            return Err(BotError::InvalidInput(format!(
+                "Prefixes can be at most {} characters long and can't contain spaces.",
+                MAX_PREFIX_LENGTH
+            ))
+            .into());
+        }
+    }
+
+    let guilds = ctx.data().storage.guilds();
+    let mut settings = guilds.get(guild_id.0).await?;
+    settings.prefix = prefix.clone();
+    guilds.save(&settings).await?;
+    ctx.data().prefixes.set(guild_id, prefix.clone());
+
+    let response = match prefix {
+        Some(prefix) => format!("Prefix set to `{}`", prefix),
+        None => format!("Prefix reset to `{}`", ctx.data().config.prefix),
+    };
+    ctx.say(response).await?;
+
+    Ok(())
+}
+
+/// Choose who can press the buttons on image embeds
+///
+/// By default anyone can reroll or browse an image someone else asked for.
+/// Anyone can always save an image to their own favourites.
+/// Example: `/config buttons everyone:false`
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn buttons(
+    ctx: Context<'_>,
+    #[description = "Let everyone use the buttons, not just whoever ran the command"]
+    everyone: bool,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("config buttons used outside a guild")?;
+    let guilds = ctx.data().storage.guilds();
+    let mut settings = guilds.get(guild_id.0).await?;
+    settings.shared_buttons = everyone;
+    guilds.save(&settings).await?;
+
+    let response = if everyone {
+        "Everyone can now use the buttons on image embeds."
+    } else {
+        "Only whoever ran the command can now use the buttons on image embeds."
+    };
+    ctx.say(response).await?;
+
+    Ok(())
+}
@@ -0,0 +1,299 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    error::BotError,
+    prefix::take_option,
+    transform::{fit, OwoStyle, Transform, MENU},
+    Context, Error,
+};
+use poise::serenity_prelude::{CollectComponentInteraction, InteractionResponseType, Message};
+use std::time::Duration;
+
+const MENU_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_ZALGO: u8 = 3;
+const MAX_ZALGO: u8 = 10;
+
+fn nothing_to_transform() -> BotError {
+    BotError::InvalidInput(String::from("There's no text to transform."))
+}
+
+/// Sends the transformed text with mentions disabled, so transforming
+/// someone's `@everyone` doesn't ping anyone.
+pub async fn say_transformed(
+    ctx: Context<'_>,
+    transform: Transform,
+    text: &str,
+) -> Result<(), Error> {
+    let transformed = transform.apply(text);
+    if transformed.trim().is_empty() {
+        return Err(nothing_to_transform().into());
+    }
+    ctx.send(|m| {
+        m.content(fit(transformed))
+            .allowed_mentions(|a| a.empty_parse())
+    })
+    .await?;
+    Ok(())
+}
+
+/// SpOnGeBoB mOcKiNg CaSe
+///
+/// Example: `/fun text mock text:i love floppa`
+#[poise::command(slash_command, prefix_command)]
+pub async fn mock(
+    ctx: Context<'_>,
+    #[description = "The text you want to mock"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    say_transformed(ctx, Transform::Mock, &text).await
+}
+
+/// 7urn 73x7 1n70 l3375p34k
+///
+/// Example: `/fun text leet text:elite hacker`
+#[poise::command(slash_command, prefix_command)]
+pub async fn leet(
+    ctx: Context<'_>,
+    #[description = "The text you want in leetspeak"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    say_transformed(ctx, Transform::Leet, &text).await
+}
+
+/// Add cursed zalgo marks to text
+///
+/// Example: `/fun text zalgo text:he comes intensity:5` or `fl.fun text zalgo he comes intensity=5`
+#[poise::command(slash_command)]
+pub async fn zalgo(
+    ctx: Context<'_>,
+    #[description = "The text you want to curse"] text: String,
+    #[description = "How cursed, from 1 to 10 (default: 3)"]
+    #[min = 1]
+    #[max = 10]
+    intensity: Option<u8>,
+) -> Result<(), Error> {
+    let intensity = intensity.unwrap_or(DEFAULT_ZALGO).clamp(1, MAX_ZALGO);
+    say_transformed(ctx, Transform::Zalgo(intensity), &text).await
+}
+
+/// The prefix form of `zalgo`, where `intensity=` can go anywhere in the
+/// text. Listed before `zalgo` so prefix lookup finds it first.
+#[poise::command(prefix_command, aliases("zalgo"), hide_in_help)]
+pub async fn zalgo_prefix(
+    ctx: Context<'_>,
+    #[description = "The text you want to curse"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    let mut text = text;
+    let intensity = match take_option(&mut text, "intensity") {
+        Some(intensity) => intensity
+            .parse()
+            .ok()
+            .filter(|intensity| (1..=MAX_ZALGO).contains(intensity))
+            .ok_or_else(|| {
+                BotError::InvalidInput(format!(
+                    "The intensity has to be a number from 1 to {}.",
+                    MAX_ZALGO
+                ))
+            })?,
+        None => DEFAULT_ZALGO,
+    };
+    say_transformed(ctx, Transform::Zalgo(intensity), &text).await
+}
+
+/// Write text backwards
+///
+/// Example: `/fun text reverse text:floppa`
+#[poise::command(slash_command, prefix_command)]
+pub async fn reverse(
+    ctx: Context<'_>,
+    #[description = "The text you want reversed"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    say_transformed(ctx, Transform::Reverse, &text).await
+}
+
+/// Turn text upside down
+///
+/// Example: `/fun text upsidedown text:australia`
+#[poise::command(slash_command, prefix_command)]
+pub async fn upsidedown(
+    ctx: Context<'_>,
+    #[description = "The text you want flipped"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    say_transformed(ctx, Transform::UpsideDown, &text).await
+}
+
+/// Ｆｕｌｌ－ｗｉｄｔｈ ｖａｐｏｒｗａｖｅ text
+///
+/// Example: `/fun text vaporwave text:aesthetic`
+#[poise::command(slash_command, prefix_command)]
+pub async fn vaporwave(
+    ctx: Context<'_>,
+    #[description = "The text you want to make aesthetic"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    say_transformed(ctx, Transform::Vaporwave, &text).await
+}
+
+/// Wʀɪᴛᴇ ɪɴ ꜱᴍᴀʟʟ ᴄᴀᴘꜱ
+///
+/// Example: `/fun text smallcaps text:tiny floppa`
+#[poise::command(slash_command, prefix_command)]
+pub async fn smallcaps(
+    ctx: Context<'_>,
+    #[description = "The text you want in small caps"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    say_transformed(ctx, Transform::SmallCaps, &text).await
+}
+
+/// Spell text with regional indicator emoji
+///
+/// Example: `/fun text emojify text:floppa`
+#[poise::command(slash_command, prefix_command)]
+pub async fn emojify(
+    ctx: Context<'_>,
+    #[description = "The text you want in emoji"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    say_transformed(ctx, Transform::RegionalIndicators, &text).await
+}
+
+/// OwO-ify text, with a choice of style
+///
+/// Example: `/fun text owo text:hello friend style:nya~` or `fl.fun text owo hello friend style=nya~`
+#[poise::command(slash_command)]
+pub async fn owo(
+    ctx: Context<'_>,
+    #[description = "The text you want to owo-ify"] text: String,
+    #[description = "Which flavour of owo (default: OwO)"] style: Option<OwoStyle>,
+) -> Result<(), Error> {
+    let style = style.unwrap_or(OwoStyle::Owo);
+    say_transformed(ctx, Transform::Owo(style), &text).await
+}
+
+/// The prefix form of `owo`, where `style=` can go anywhere in the text.
+/// Listed before `owo` so prefix lookup finds it first.
+#[poise::command(prefix_command, aliases("owo"), hide_in_help)]
+pub async fn owo_prefix(
+    ctx: Context<'_>,
+    #[description = "The text you want to owo-ify"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    let mut text = text;
+    let style = match take_option(&mut text, "style") {
+        Some(style) => owo_style(&style)?,
+        None => OwoStyle::Owo,
+    };
+    say_transformed(ctx, Transform::Owo(style), &text).await
+}
+
+fn owo_style(name: &str) -> Result<OwoStyle, BotError> {
+    match name.to_lowercase().as_str() {
+        "owo" => Ok(OwoStyle::Owo),
+        "uwu" => Ok(OwoStyle::Uwu),
+        "nya" | "nya~" => Ok(OwoStyle::Nya),
+        _ => Err(BotError::InvalidInput(format!(
+            "There's no owo style called {:?}, try OwO, UwU or nya~.",
+            name
+        ))),
+    }
+}
+
+#[poise::command(context_menu_command = "Mock")]
+pub async fn mock_message(ctx: Context<'_>, msg: Message) -> Result<(), Error> {
+    say_transformed(ctx, Transform::Mock, &msg.content).await
+}
+
+/// Pick a transformation for someone's message from a menu. The menu is only
+/// visible to whoever opened it, the result is posted publicly.
+#[poise::command(context_menu_command = "Transform text")]
+pub async fn transform_message(ctx: Context<'_>, msg: Message) -> Result<(), Error> {
+    if msg.content.trim().is_empty() {
+        return Err(nothing_to_transform().into());
+    }
+    let menu_id = ctx.id().to_string();
+    let reply = ctx
+        .send(|m| {
+            m.ephemeral(true)
+                .content("Pick a transformation:")
+                .components(|c| {
+                    c.create_action_row(|ar| {
+                        ar.create_select_menu(|s| {
+                            s.custom_id(&menu_id)
+                                .placeholder("Transformation")
+                                .options(|o| {
+                                    for (index, (label, _)) in MENU.iter().enumerate() {
+                                        o.create_option(|opt| opt.label(label).value(index));
+                                    }
+                                    o
+                                })
+                        })
+                    })
+                })
+        })
+        .await?;
+
+    let mci = match CollectComponentInteraction::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(MENU_TIMEOUT)
+        .filter(move |mci| mci.data.custom_id == menu_id)
+        .await
+    {
+        Some(mci) => mci,
+        None => {
+            reply
+                .edit(ctx, |m| {
+                    m.content("No transformation picked.").components(|c| c)
+                })
+                .await?;
+            return Ok(());
+        }
+    };
+    let transform = mci
+        .data
+        .values
+        .first()
+        .and_then(|value| value.parse::<usize>().ok())
+        .and_then(|index| MENU.get(index))
+        .map(|(_, transform)| *transform)
+        .ok_or("unknown transformation picked")?;
+
+    let transformed = fit(transform.apply(&msg.content));
+    mci.create_interaction_response(ctx, |ir| {
+        ir.kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|d| {
+                d.content(transformed).allowed_mentions(|a| a.empty_parse())
+            })
+    })
+    .await?;
+    reply
+        .edit(ctx, |m| m.content("Done!").components(|c| c))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,495 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{http::HttpOptions, providers, Data, Error};
+use serde::{Deserialize, Deserializer};
+use std::{collections::HashMap, env, fmt, fs, io, path::PathBuf, time::Duration};
+
+const DEFAULT_PATH: &str = "floppabot.toml";
+
+/// Everything that used to live in `secrets.rs`, plus the knobs that differ
+/// between deployments. Loaded from `floppabot.toml` (or `FLOPPABOT_CONFIG`),
+/// then overridden by environment variables.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub discord_token: String,
+    pub prefix: String,
+    pub owners: Vec<u64>,
+    pub database_url: String,
+    /// Top-level commands to register. `None` registers all of them.
+    pub commands: Option<Vec<String>>,
+    pub api_keys: ApiKeys,
+    pub http: HttpConfig,
+    pub prefetch: PrefetchConfig,
+    pub duplicates: DuplicatesConfig,
+    pub cooldowns: CooldownsConfig,
+    /// Requests per image API, keyed by provider name (e.g. `thecatapi`).
+    /// Entries in the file go over the defaults, APIs that aren't listed in
+    /// either aren't limited.
+    #[serde(deserialize_with = "merge_rate_limits")]
+    pub rate_limits: HashMap<String, RateLimit>,
+    pub endpoints: Endpoints,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeys {
+    pub cat: String,
+    pub dog: String,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub user_agent: Option<String>,
+    pub timeout_secs: u64,
+}
+
+/// How many images to keep fetched ahead of time per provider, 0 turns
+/// prefetching off.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrefetchConfig {
+    pub size: usize,
+    pub ttl_secs: u64,
+}
+
+/// How many of a channel's latest images are remembered, and for how long, so
+/// the same picture isn't shown there twice in a row. A `window` of 0 turns
+/// this off.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DuplicatesConfig {
+    pub window: usize,
+    pub ttl_secs: u64,
+}
+
+/// `default` applies to every command without an entry in `commands`, which
+/// is keyed by qualified name ("animals cat") or top-level name ("animals").
+/// The New button on image embeds goes by `reroll`.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CooldownsConfig {
+    pub default: Cooldown,
+    pub commands: HashMap<String, Cooldown>,
+}
+
+/// Seconds before the same user, channel or guild can use a command again,
+/// 0 for no cooldown.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cooldown {
+    pub user_secs: u64,
+    pub channel_secs: u64,
+    pub guild_secs: u64,
+}
+
+/// A token bucket: `burst` requests right away, then `per_minute`.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Endpoints {
+    pub thecatapi: String,
+    pub thedogapi: String,
+    pub randomfox: String,
+    pub shibe: String,
+    pub awscat: String,
+    pub randomdog: String,
+    pub randomduck: String,
+    pub nekosbest: String,
+    pub waifupics: String,
+    pub purrbot: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            discord_token: String::new(),
+            prefix: String::from("fl."),
+            owners: Vec::new(),
+            database_url: String::from("sqlite://floppabot.db"),
+            commands: None,
+            api_keys: ApiKeys::default(),
+            http: HttpConfig::default(),
+            prefetch: PrefetchConfig::default(),
+            duplicates: DuplicatesConfig::default(),
+            cooldowns: CooldownsConfig::default(),
+            rate_limits: default_rate_limits(),
+            endpoints: Endpoints::default(),
+        }
+    }
+}
+
+fn default_rate_limits() -> HashMap<String, RateLimit> {
+    let quota = RateLimit {
+        per_minute: 30,
+        burst: 10,
+    };
+    HashMap::from([
+        (String::from("thecatapi"), quota),
+        (String::from("thedogapi"), quota),
+    ])
+}
+
+/// Limiting one API in the file shouldn't drop the default limits of the
+/// others.
+fn merge_rate_limits<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, RateLimit>, D::Error> {
+    let mut limits = default_rate_limits();
+    limits.extend(HashMap::<String, RateLimit>::deserialize(deserializer)?);
+    Ok(limits)
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: None,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            size: 3,
+            ttl_secs: 600,
+        }
+    }
+}
+
+impl Default for DuplicatesConfig {
+    fn default() -> Self {
+        Self {
+            window: 25,
+            ttl_secs: 3600,
+        }
+    }
+}
+
+impl Default for CooldownsConfig {
+    fn default() -> Self {
+        Self {
+            default: Cooldown {
+                user_secs: 2,
+                ..Cooldown::default()
+            },
+            commands: HashMap::new(),
+        }
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            thecatapi: String::from("https://api.thecatapi.com/v1"),
+            thedogapi: String::from("https://api.thedogapi.com/v1"),
+            randomfox: String::from("https://randomfox.ca"),
+            shibe: String::from("http://shibe.online"),
+            awscat: String::from("https://aws.random.cat"),
+            randomdog: String::from("https://random.dog"),
+            randomduck: String::from("https://random-d.uk"),
+            nekosbest: String::from("https://nekos.best"),
+            waifupics: String::from("https://api.waifu.pics"),
+            purrbot: String::from("https://purrbot.site"),
+        }
+    }
+}
+
+impl Endpoints {
+    fn iter_mut(&mut self) -> impl Iterator<Item = (&'static str, &mut String)> {
+        [
+            ("thecatapi", &mut self.thecatapi),
+            ("thedogapi", &mut self.thedogapi),
+            ("randomfox", &mut self.randomfox),
+            ("shibe", &mut self.shibe),
+            ("awscat", &mut self.awscat),
+            ("randomdog", &mut self.randomdog),
+            ("randomduck", &mut self.randomduck),
+            ("nekosbest", &mut self.nekosbest),
+            ("waifupics", &mut self.waifupics),
+            ("purrbot", &mut self.purrbot),
+        ]
+        .into_iter()
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    InvalidEnv(&'static str, String),
+    Invalid(String),
+    UnknownCommand(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => {
+                write!(f, "couldn't read config file {}: {}", path.display(), err)
+            }
+            ConfigError::Parse(path, err) => {
+                write!(f, "couldn't parse config file {}: {}", path.display(), err)
+            }
+            ConfigError::InvalidEnv(var, message) => write!(f, "{} is invalid: {}", var, message),
+            ConfigError::Invalid(message) => write!(f, "invalid config: {}", message),
+            ConfigError::UnknownCommand(name) => write!(
+                f,
+                "invalid config: `commands` lists {:?}, which is not a command",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads the config file if there is one, applies environment overrides
+    /// and validates the result. A missing file is only an error when
+    /// `FLOPPABOT_CONFIG` points at it explicitly.
+    pub fn load() -> Result<Self, ConfigError> {
+        let explicit = env::var_os("FLOPPABOT_CONFIG").map(PathBuf::from);
+        let path = explicit
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_PATH));
+        let mut config = match fs::read_to_string(&path) {
+            Ok(contents) => {
+                Self::from_toml(&contents).map_err(|err| ConfigError::Parse(path, err))?
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound && explicit.is_none() => {
+                Self::default()
+            }
+            Err(err) => return Err(ConfigError::Read(path, err)),
+        };
+        config.apply_env(|var| env::var(var).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Applies environment variable overrides. `lookup` is normally a thin
+    /// wrapper around `std::env::var`.
+    pub fn apply_env(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), ConfigError> {
+        if let Some(token) = lookup("DISCORD_TOKEN") {
+            self.discord_token = token;
+        }
+        if let Some(key) = lookup("CAT_API_KEY") {
+            self.api_keys.cat = key;
+        }
+        if let Some(key) = lookup("DOG_API_KEY") {
+            self.api_keys.dog = key;
+        }
+        if let Some(prefix) = lookup("FLOPPABOT_PREFIX") {
+            self.prefix = prefix;
+        }
+        if let Some(owners) = lookup("FLOPPABOT_OWNERS") {
+            self.owners = split_list(&owners)
+                .map(|id| {
+                    id.parse().map_err(|_| {
+                        ConfigError::InvalidEnv(
+                            "FLOPPABOT_OWNERS",
+                            format!("{:?} is not a user id", id),
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(url) = lookup("DATABASE_URL") {
+            self.database_url = url;
+        }
+        if let Some(commands) = lookup("FLOPPABOT_COMMANDS") {
+            self.commands = Some(split_list(&commands).map(String::from).collect());
+        }
+        if let Some(user_agent) = lookup("FLOPPABOT_USER_AGENT") {
+            self.http.user_agent = Some(user_agent);
+        }
+        if let Some(timeout) = lookup("FLOPPABOT_HTTP_TIMEOUT") {
+            self.http.timeout_secs = timeout.parse().map_err(|_| {
+                ConfigError::InvalidEnv("FLOPPABOT_HTTP_TIMEOUT", String::from("expected seconds"))
+            })?;
+        }
+        if let Some(size) = lookup("FLOPPABOT_PREFETCH_SIZE") {
+            self.prefetch.size = size.parse().map_err(|_| {
+                ConfigError::InvalidEnv(
+                    "FLOPPABOT_PREFETCH_SIZE",
+                    String::from("expected a number"),
+                )
+            })?;
+        }
+        if let Some(ttl) = lookup("FLOPPABOT_PREFETCH_TTL") {
+            self.prefetch.ttl_secs = ttl.parse().map_err(|_| {
+                ConfigError::InvalidEnv("FLOPPABOT_PREFETCH_TTL", String::from("expected seconds"))
+            })?;
+        }
+        if let Some(window) = lookup("FLOPPABOT_DUPLICATES_WINDOW") {
+            self.duplicates.window = window.parse().map_err(|_| {
+                ConfigError::InvalidEnv(
+                    "FLOPPABOT_DUPLICATES_WINDOW",
+                    String::from("expected a number"),
+                )
+            })?;
+        }
+        if let Some(ttl) = lookup("FLOPPABOT_DUPLICATES_TTL") {
+            self.duplicates.ttl_secs = ttl.parse().map_err(|_| {
+                ConfigError::InvalidEnv(
+                    "FLOPPABOT_DUPLICATES_TTL",
+                    String::from("expected seconds"),
+                )
+            })?;
+        }
+        for (var, secs) in [
+            (
+                "FLOPPABOT_COOLDOWN_USER",
+                &mut self.cooldowns.default.user_secs,
+            ),
+            (
+                "FLOPPABOT_COOLDOWN_CHANNEL",
+                &mut self.cooldowns.default.channel_secs,
+            ),
+            (
+                "FLOPPABOT_COOLDOWN_GUILD",
+                &mut self.cooldowns.default.guild_secs,
+            ),
+        ] {
+            if let Some(value) = lookup(var) {
+                *secs = value
+                    .parse()
+                    .map_err(|_| ConfigError::InvalidEnv(var, String::from("expected seconds")))?;
+            }
+        }
+        for (name, endpoint) in self.endpoints.iter_mut() {
+            let var = format!("FLOPPABOT_ENDPOINT_{}", name.to_uppercase());
+            if let Some(url) = lookup(&var) {
+                *endpoint = url;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate(&mut self) -> Result<(), ConfigError> {
+        if self.discord_token.trim().is_empty() {
+            return Err(ConfigError::Invalid(String::from(
+                "no Discord token, set `discord_token` or DISCORD_TOKEN",
+            )));
+        }
+        if self.prefix.is_empty() || self.prefix.chars().any(char::is_whitespace) {
+            return Err(ConfigError::Invalid(format!(
+                "prefix {:?} must be non-empty and contain no whitespace",
+                self.prefix
+            )));
+        }
+        if self.http.timeout_secs == 0 {
+            return Err(ConfigError::Invalid(String::from(
+                "`http.timeout_secs` must be at least 1",
+            )));
+        }
+        if self.prefetch.ttl_secs == 0 {
+            return Err(ConfigError::Invalid(String::from(
+                "`prefetch.ttl_secs` must be at least 1",
+            )));
+        }
+        if self.duplicates.ttl_secs == 0 {
+            return Err(ConfigError::Invalid(String::from(
+                "`duplicates.ttl_secs` must be at least 1",
+            )));
+        }
+        let known = providers::base_registry(self);
+        for (name, limit) in &self.rate_limits {
+            if !known.contains(name) {
+                return Err(ConfigError::Invalid(format!(
+                    "`rate_limits` lists {:?}, which is not an image API",
+                    name
+                )));
+            }
+            if limit.per_minute == 0 || limit.burst == 0 {
+                return Err(ConfigError::Invalid(format!(
+                    "`rate_limits.{}` needs a `per_minute` and `burst` of at least 1",
+                    name
+                )));
+            }
+        }
+        for (name, endpoint) in self.endpoints.iter_mut() {
+            let trimmed = endpoint.trim_end_matches('/');
+            if let Err(err) = reqwest::Url::parse(trimmed) {
+                return Err(ConfigError::Invalid(format!(
+                    "endpoint `{}` ({:?}) is not a valid URL: {}",
+                    name, endpoint, err
+                )));
+            }
+            *endpoint = trimmed.to_string();
+        }
+        Ok(())
+    }
+
+    pub fn prefetch_ttl(&self) -> Duration {
+        Duration::from_secs(self.prefetch.ttl_secs)
+    }
+
+    pub fn duplicates_ttl(&self) -> Duration {
+        Duration::from_secs(self.duplicates.ttl_secs)
+    }
+
+    pub fn http_options(&self) -> HttpOptions {
+        let mut options = HttpOptions {
+            timeout: Duration::from_secs(self.http.timeout_secs),
+            ..HttpOptions::default()
+        };
+        if let Some(user_agent) = &self.http.user_agent {
+            options.user_agent = user_agent.clone();
+        }
+        options
+    }
+
+    /// Drops every top-level command not listed in `commands`, failing on
+    /// names that don't match any command so typos don't go unnoticed.
+    pub fn filter_commands(
+        &self,
+        commands: &mut Vec<poise::Command<Data, Error>>,
+    ) -> Result<(), ConfigError> {
+        let enabled = match &self.commands {
+            Some(enabled) => enabled,
+            None => return Ok(()),
+        };
+        if let Some(unknown) = enabled
+            .iter()
+            .find(|name| !commands.iter().any(|command| &command.name == *name))
+        {
+            return Err(ConfigError::UnknownCommand(unknown.clone()));
+        }
+        commands.retain(|command| enabled.contains(&command.name));
+        Ok(())
+    }
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
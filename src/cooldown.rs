@@ -0,0 +1,176 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    config::{Cooldown, CooldownsConfig},
+    error::BotError,
+    Context, Error,
+};
+use poise::{
+    serenity_prelude::{ChannelId, GuildId, UserId},
+    ApplicationCommandOrAutocompleteInteraction,
+};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Running cooldowns kept before the finished ones are swept out.
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Scope {
+    User,
+    Channel,
+    Guild,
+}
+
+/// When each user, channel and guild can use each command again. Buttons
+/// that cost an API request go through this too, under their own name.
+pub struct Cooldowns {
+    config: CooldownsConfig,
+    until: Mutex<HashMap<(Scope, u64, String), Instant>>,
+}
+
+impl Cooldowns {
+    pub fn new(config: CooldownsConfig) -> Self {
+        Self {
+            config,
+            until: Mutex::default(),
+        }
+    }
+
+    fn cooldown(&self, name: &str) -> Cooldown {
+        let top_level = name.split(' ').next().unwrap_or(name);
+        self.config
+            .commands
+            .get(name)
+            .or_else(|| self.config.commands.get(top_level))
+            .copied()
+            .unwrap_or(self.config.default)
+    }
+
+    fn scopes(
+        &self,
+        name: &str,
+        user: UserId,
+        channel: ChannelId,
+        guild: Option<GuildId>,
+    ) -> Vec<(Scope, u64, u64)> {
+        let cooldown = self.cooldown(name);
+        let mut scopes = vec![
+            (Scope::User, user.0, cooldown.user_secs),
+            (Scope::Channel, channel.0, cooldown.channel_secs),
+        ];
+        if let Some(guild) = guild {
+            scopes.push((Scope::Guild, guild.0, cooldown.guild_secs));
+        }
+        scopes
+    }
+
+    /// Says how long is left when a cooldown of `name` is still running for
+    /// this user, channel or guild, without starting any.
+    pub fn ready(
+        &self,
+        name: &str,
+        user: UserId,
+        channel: ChannelId,
+        guild: Option<GuildId>,
+    ) -> Result<(), BotError> {
+        let scopes = self.scopes(name, user, channel, guild);
+        remaining(&self.until.lock().unwrap(), name, &scopes)
+    }
+
+    /// Starts the cooldowns of `name` for this user, channel and guild.
+    pub fn start(&self, name: &str, user: UserId, channel: ChannelId, guild: Option<GuildId>) {
+        let scopes = self.scopes(name, user, channel, guild);
+        start(&mut self.until.lock().unwrap(), name, scopes);
+    }
+
+    /// `ready` and `start` at once, for buttons that don't go through the
+    /// framework's checks.
+    pub fn check(
+        &self,
+        name: &str,
+        user: UserId,
+        channel: ChannelId,
+        guild: Option<GuildId>,
+    ) -> Result<(), BotError> {
+        let scopes = self.scopes(name, user, channel, guild);
+        let mut until = self.until.lock().unwrap();
+        remaining(&until, name, &scopes)?;
+        start(&mut until, name, scopes);
+        Ok(())
+    }
+}
+
+type Until = HashMap<(Scope, u64, String), Instant>;
+
+fn remaining(until: &Until, name: &str, scopes: &[(Scope, u64, u64)]) -> Result<(), BotError> {
+    let now = Instant::now();
+    let remaining = scopes
+        .iter()
+        .filter_map(|(scope, id, _)| until.get(&(*scope, *id, name.to_string())))
+        .map(|until| until.saturating_duration_since(now))
+        .max()
+        .unwrap_or_default();
+    if remaining.is_zero() {
+        Ok(())
+    } else {
+        Err(BotError::RateLimited(remaining))
+    }
+}
+
+fn start(until: &mut Until, name: &str, scopes: Vec<(Scope, u64, u64)>) {
+    let now = Instant::now();
+    if until.len() >= MAX_ENTRIES {
+        until.retain(|_, until| *until > now);
+    }
+    for (scope, id, secs) in scopes {
+        if secs > 0 {
+            until.insert(
+                (scope, id, name.to_string()),
+                now + Duration::from_secs(secs),
+            );
+        }
+    }
+}
+
+/// Runs once per parent command and, for slash commands, more than once per
+/// invocation, so it only looks. `pre_command` starts the cooldowns.
+/// Autocomplete isn't held up by them either.
+pub async fn command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    if let poise::Context::Application(ctx) = ctx {
+        if let ApplicationCommandOrAutocompleteInteraction::Autocomplete(_) = ctx.interaction {
+            return Ok(true);
+        }
+    }
+    ctx.data().cooldowns.ready(
+        &ctx.command().qualified_name,
+        ctx.author().id,
+        ctx.channel_id(),
+        ctx.guild_id(),
+    )?;
+    Ok(true)
+}
+
+pub async fn pre_command(ctx: Context<'_>) {
+    ctx.data().cooldowns.start(
+        &ctx.command().qualified_name,
+        ctx.author().id,
+        ctx.channel_id(),
+        ctx.guild_id(),
+    );
+}
@@ -0,0 +1,193 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Data, Error};
+use poise::serenity_prelude::CreateEmbed;
+use std::{fmt, time::Duration};
+
+const ERROR_COLOUR: u32 = 0xED4245;
+
+/// Errors we expect to happen and want to explain to the user, as opposed to
+/// bugs, which only get a generic message.
+#[derive(Debug)]
+pub enum BotError {
+    Upstream {
+        service: String,
+        status: Option<u16>,
+        message: String,
+    },
+    Decode {
+        service: String,
+        message: String,
+    },
+    Timeout {
+        service: String,
+    },
+    InvalidInput(String),
+    MissingPermission(String),
+    /// A cooldown or an image API's rate limit, with how long until it's over.
+    RateLimited(Duration),
+}
+
+impl BotError {
+    pub fn from_reqwest(err: reqwest::Error) -> Self {
+        let service = err
+            .url()
+            .and_then(|url| url.host_str())
+            .unwrap_or("an image API")
+            .to_string();
+        if err.is_timeout() {
+            BotError::Timeout { service }
+        } else if err.is_decode() {
+            BotError::Decode {
+                service,
+                message: err.to_string(),
+            }
+        } else {
+            BotError::Upstream {
+                service,
+                status: err.status().map(|status| status.as_u16()),
+                message: err.to_string(),
+            }
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            BotError::Upstream { .. } => "Image service unavailable",
+            BotError::Decode { .. } => "Unexpected response",
+            BotError::Timeout { .. } => "Image service timed out",
+            BotError::InvalidInput(_) => "Invalid input",
+            BotError::MissingPermission(_) => "Missing permission",
+            BotError::RateLimited(_) => "Slow down",
+        }
+    }
+
+    fn user_message(&self) -> String {
+        match self {
+            BotError::Upstream {
+                service,
+                status: Some(status),
+                ..
+            } => format!(
+                "{} answered with HTTP {}, try again later.",
+                service, status
+            ),
+            BotError::Upstream { service, .. } => {
+                format!("Couldn't reach {}, try again later.", service)
+            }
+            BotError::Decode { service, .. } => {
+                format!("{} sent something we couldn't understand.", service)
+            }
+            BotError::Timeout { service } => {
+                format!("{} took too long to answer, try again later.", service)
+            }
+            BotError::InvalidInput(message) => message.clone(),
+            BotError::MissingPermission(permission) => {
+                format!("You need the {} permission to do that.", permission)
+            }
+            BotError::RateLimited(retry_after) => format!(
+                "Give it a moment and try again in {}s.",
+                retry_after.as_secs_f64().ceil().max(1.0)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotError::Upstream {
+                service,
+                status,
+                message,
+            } => write!(
+                f,
+                "upstream error from {} ({:?}): {}",
+                service, status, message
+            ),
+            BotError::Decode { service, message } => {
+                write!(f, "couldn't decode response from {}: {}", service, message)
+            }
+            BotError::Timeout { service } => write!(f, "request to {} timed out", service),
+            BotError::InvalidInput(message) => write!(f, "invalid input: {}", message),
+            BotError::MissingPermission(permission) => {
+                write!(f, "missing permission: {}", permission)
+            }
+            BotError::RateLimited(retry_after) => {
+                write!(f, "rate limited for another {:?}", retry_after)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
+
+impl From<reqwest::Error> for BotError {
+    fn from(err: reqwest::Error) -> Self {
+        BotError::from_reqwest(err)
+    }
+}
+
+pub fn error_embed<'a>(e: &'a mut CreateEmbed, err: &Error) -> &'a mut CreateEmbed {
+    match err.downcast_ref::<BotError>() {
+        Some(err) => e.title(err.title()).description(err.user_message()),
+        None => e
+            .title("Something went wrong")
+            .description("An unexpected error occurred while running this command."),
+    }
+    .colour(ERROR_COLOUR)
+}
+
+async fn reply_with_error(ctx: crate::Context<'_>, err: &Error) {
+    if let Err(send_err) = ctx
+        .send(|m| m.ephemeral(true).embed(|e| error_embed(e, err)))
+        .await
+    {
+        tracing::error!("Failed to report error to user: {}", send_err);
+    }
+}
+
+pub async fn on_error(err: poise::FrameworkError<'_, Data, Error>) {
+    match err {
+        poise::FrameworkError::Command { error, ctx } => {
+            tracing::error!(
+                command = %ctx.command().qualified_name,
+                user = %ctx.author().id,
+                "Command failed: {}",
+                error
+            );
+            reply_with_error(ctx, &error).await;
+        }
+        poise::FrameworkError::MissingUserPermissions {
+            missing_permissions,
+            ctx,
+        } => {
+            let permission = missing_permissions
+                .map(|permissions| permissions.to_string())
+                .unwrap_or_else(|| String::from("required"));
+            let error: Error = Box::new(BotError::MissingPermission(permission));
+            reply_with_error(ctx, &error).await;
+        }
+        poise::FrameworkError::CommandCheckFailed {
+            error: Some(error),
+            ctx,
+        } => reply_with_error(ctx, &error).await,
+        err => {
+            if let Err(e) = poise::builtins::on_error(err).await {
+                tracing::error!("Error while handling error: {}", e);
+            }
+        }
+    }
+}
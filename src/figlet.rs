@@ -0,0 +1,172 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{error::BotError, Error};
+use figlet_rs::FIGfont;
+use std::str::FromStr;
+
+#[derive(Clone, Copy)]
+pub struct Font {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// `None` is figlet-rs' built-in standard font.
+    source: Option<&'static str>,
+}
+
+/// Fonts bundled with the bot. Everything except `standard` lives in `fonts/`.
+pub static FONTS: &[Font] = &[
+    Font {
+        name: "standard",
+        description: "The classic FIGlet font",
+        source: None,
+    },
+    Font {
+        name: "blocks",
+        description: "Big solid letters",
+        source: Some(include_str!("../fonts/blocks.flf")),
+    },
+    Font {
+        name: "serif",
+        description: "Solid letters with serifs",
+        source: Some(include_str!("../fonts/serif.flf")),
+    },
+    Font {
+        name: "mini",
+        description: "Small solid letters, fits more per line",
+        source: Some(include_str!("../fonts/mini.flf")),
+    },
+    Font {
+        name: "hash",
+        description: "Letters drawn with #",
+        source: Some(include_str!("../fonts/hash.flf")),
+    },
+];
+
+impl Font {
+    pub fn load(&self) -> Result<FIGfont, Error> {
+        let font = match self.source {
+            Some(source) => FIGfont::from_content(source)?,
+            None => FIGfont::standard()?,
+        };
+        Ok(font)
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        FONTS[0]
+    }
+}
+
+impl FromStr for Font {
+    type Err = BotError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        FONTS
+            .iter()
+            .find(|font| font.name.eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| BotError::InvalidInput(format!("There's no font called {:?}.", name)))
+    }
+}
+
+pub fn search(partial: &str) -> impl Iterator<Item = &'static Font> {
+    let partial = partial.to_lowercase();
+    FONTS
+        .iter()
+        .filter(move |font| font.name.contains(&partial))
+}
+
+fn unrenderable() -> BotError {
+    BotError::InvalidInput(String::from("That text can't be turned into ASCII art."))
+}
+
+fn draw(font: &FIGfont, text: &str) -> Option<Vec<String>> {
+    let figure = font.convert(text)?;
+    Some(
+        figure
+            .to_string()
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .collect(),
+    )
+}
+
+fn drawn_width(font: &FIGfont, text: &str) -> usize {
+    draw(font, text)
+        .map(|lines| {
+            lines
+                .iter()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0)
+}
+
+/// Splits a word that is too wide on its own into pieces that fit.
+fn split_word(font: &FIGfont, word: &str, width: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    for c in word.chars() {
+        piece.push(c);
+        if piece.chars().count() > 1 && drawn_width(font, &piece) > width {
+            piece.pop();
+            pieces.push(std::mem::take(&mut piece));
+            piece.push(c);
+        }
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Renders `text`, wrapping words so no line of the art is wider than
+/// `width` columns. Each wrapped line is drawn separately, one under another.
+pub fn render(font: &FIGfont, text: &str, width: usize) -> Result<String, BotError> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let pieces = if drawn_width(font, word) > width {
+            split_word(font, word, width)
+        } else {
+            vec![word.to_string()]
+        };
+        for piece in pieces {
+            let candidate = if line.is_empty() {
+                piece.clone()
+            } else {
+                format!("{} {}", line, piece)
+            };
+            if line.is_empty() || drawn_width(font, &candidate) <= width {
+                line = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut line, piece));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    let mut art = Vec::new();
+    for line in &lines {
+        art.extend(draw(font, line).ok_or_else(unrenderable)?);
+    }
+    if art.iter().all(|line| line.is_empty()) {
+        return Err(unrenderable());
+    }
+    Ok(art.join("\n"))
+}
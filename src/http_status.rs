@@ -0,0 +1,111 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub struct HttpStatus {
+    pub code: u16,
+    pub reason: &'static str,
+    pub description: &'static str,
+    pub reference: &'static str,
+}
+
+macro_rules! status {
+    ($code:expr, $reason:expr, $reference:expr, $description:expr) => {
+        HttpStatus {
+            code: $code,
+            reason: $reason,
+            description: $description,
+            reference: $reference,
+        }
+    };
+}
+
+/// Registered status codes (IANA HTTP Status Code Registry), plus 418 which
+/// both http.cat and http.dog have a picture for.
+pub static STATUSES: &[HttpStatus] = &[
+    status!(100, "Continue", "RFC 9110, 15.2.1", "The initial part of the request was received and the client should continue."),
+    status!(101, "Switching Protocols", "RFC 9110, 15.2.2", "The server is switching to the protocol the client asked for in the Upgrade header."),
+    status!(102, "Processing", "RFC 2518, 10.1", "The server accepted the request but hasn't finished processing it yet."),
+    status!(103, "Early Hints", "RFC 8297", "Headers the client can use to start preloading resources while the final response is prepared."),
+    status!(200, "OK", "RFC 9110, 15.3.1", "The request succeeded."),
+    status!(201, "Created", "RFC 9110, 15.3.2", "The request succeeded and a new resource was created."),
+    status!(202, "Accepted", "RFC 9110, 15.3.3", "The request was accepted for processing, but processing hasn't completed."),
+    status!(203, "Non-Authoritative Information", "RFC 9110, 15.3.4", "The request succeeded, but a transforming proxy modified the response."),
+    status!(204, "No Content", "RFC 9110, 15.3.5", "The request succeeded and there is no content to send back."),
+    status!(205, "Reset Content", "RFC 9110, 15.3.6", "The request succeeded and the client should reset the document view."),
+    status!(206, "Partial Content", "RFC 9110, 15.3.7", "The server is delivering only the range of the resource the client asked for."),
+    status!(207, "Multi-Status", "RFC 4918, 11.1", "The body contains status information for multiple independent operations."),
+    status!(208, "Already Reported", "RFC 5842, 7.1", "The members of a DAV binding were already listed earlier in this response."),
+    status!(226, "IM Used", "RFC 3229, 10.4.1", "The response is the result of instance manipulations applied to the current instance."),
+    status!(300, "Multiple Choices", "RFC 9110, 15.4.1", "The resource has several representations and the client should pick one."),
+    status!(301, "Moved Permanently", "RFC 9110, 15.4.2", "The resource has a new permanent URL, given in the Location header."),
+    status!(302, "Found", "RFC 9110, 15.4.3", "The resource temporarily lives at a different URL."),
+    status!(303, "See Other", "RFC 9110, 15.4.4", "The client should GET a different URL to see the result of its request."),
+    status!(304, "Not Modified", "RFC 9110, 15.4.5", "The cached copy the client already has is still valid."),
+    status!(305, "Use Proxy", "RFC 9110, 15.4.6", "Deprecated. The resource had to be accessed through a proxy."),
+    status!(307, "Temporary Redirect", "RFC 9110, 15.4.8", "Repeat the request at another URL without changing the method."),
+    status!(308, "Permanent Redirect", "RFC 9110, 15.4.9", "The resource permanently moved; repeat the request there without changing the method."),
+    status!(400, "Bad Request", "RFC 9110, 15.5.1", "The server can't process the request because of a client error."),
+    status!(401, "Unauthorized", "RFC 9110, 15.5.2", "The request lacks valid authentication credentials."),
+    status!(402, "Payment Required", "RFC 9110, 15.5.3", "Reserved for future use, sometimes used for paywalls and quotas."),
+    status!(403, "Forbidden", "RFC 9110, 15.5.4", "The server understood the request but refuses to fulfil it."),
+    status!(404, "Not Found", "RFC 9110, 15.5.5", "The server can't find the requested resource."),
+    status!(405, "Method Not Allowed", "RFC 9110, 15.5.6", "The resource doesn't support the request method."),
+    status!(406, "Not Acceptable", "RFC 9110, 15.5.7", "No representation matches the client's Accept headers."),
+    status!(407, "Proxy Authentication Required", "RFC 9110, 15.5.8", "The client must authenticate with the proxy first."),
+    status!(408, "Request Timeout", "RFC 9110, 15.5.9", "The server gave up waiting for the client to finish the request."),
+    status!(409, "Conflict", "RFC 9110, 15.5.10", "The request conflicts with the current state of the resource."),
+    status!(410, "Gone", "RFC 9110, 15.5.11", "The resource existed but was permanently removed."),
+    status!(411, "Length Required", "RFC 9110, 15.5.12", "The server requires a Content-Length header."),
+    status!(412, "Precondition Failed", "RFC 9110, 15.5.13", "A precondition in the request headers evaluated to false."),
+    status!(413, "Content Too Large", "RFC 9110, 15.5.14", "The request body is larger than the server is willing to process."),
+    status!(414, "URI Too Long", "RFC 9110, 15.5.15", "The request URI is longer than the server is willing to interpret."),
+    status!(415, "Unsupported Media Type", "RFC 9110, 15.5.16", "The server doesn't support the format of the request body."),
+    status!(416, "Range Not Satisfiable", "RFC 9110, 15.5.17", "The requested range can't be served for this resource."),
+    status!(417, "Expectation Failed", "RFC 9110, 15.5.18", "The server can't meet the Expect request header."),
+    status!(418, "I'm a teapot", "RFC 2324, 2.3.2", "The server refuses to brew coffee because it is, permanently, a teapot."),
+    status!(421, "Misdirected Request", "RFC 9110, 15.5.20", "The request reached a server that can't produce a response for it."),
+    status!(422, "Unprocessable Content", "RFC 9110, 15.5.21", "The request is well-formed but has semantic errors."),
+    status!(423, "Locked", "RFC 4918, 11.3", "The resource is locked."),
+    status!(424, "Failed Dependency", "RFC 4918, 11.4", "The request failed because a request it depended on failed."),
+    status!(425, "Too Early", "RFC 8470, 5.2", "The server won't risk processing a request that might be replayed."),
+    status!(426, "Upgrade Required", "RFC 9110, 15.5.22", "The client should switch to a different protocol."),
+    status!(428, "Precondition Required", "RFC 6585, 3", "The server requires the request to be conditional."),
+    status!(429, "Too Many Requests", "RFC 6585, 4", "The client sent too many requests in a given amount of time."),
+    status!(431, "Request Header Fields Too Large", "RFC 6585, 5", "The request headers are too large."),
+    status!(451, "Unavailable For Legal Reasons", "RFC 7725, 3", "The resource can't be served for legal reasons."),
+    status!(500, "Internal Server Error", "RFC 9110, 15.6.1", "The server hit an unexpected condition."),
+    status!(501, "Not Implemented", "RFC 9110, 15.6.2", "The server doesn't support the functionality needed for the request."),
+    status!(502, "Bad Gateway", "RFC 9110, 15.6.3", "A gateway or proxy got an invalid response from upstream."),
+    status!(503, "Service Unavailable", "RFC 9110, 15.6.4", "The server is temporarily overloaded or down for maintenance."),
+    status!(504, "Gateway Timeout", "RFC 9110, 15.6.5", "A gateway or proxy didn't get a response from upstream in time."),
+    status!(505, "HTTP Version Not Supported", "RFC 9110, 15.6.6", "The server doesn't support the HTTP version of the request."),
+    status!(506, "Variant Also Negotiates", "RFC 2295, 8.1", "The server has a configuration error in content negotiation."),
+    status!(507, "Insufficient Storage", "RFC 4918, 11.5", "The server can't store what it needs to complete the request."),
+    status!(508, "Loop Detected", "RFC 5842, 7.2", "The server found an infinite loop while processing the request."),
+    status!(510, "Not Extended", "RFC 2774, 7", "Further extensions to the request are required."),
+    status!(511, "Network Authentication Required", "RFC 6585, 6", "The client needs to authenticate to gain network access."),
+];
+
+pub fn lookup(code: u16) -> Option<&'static HttpStatus> {
+    STATUSES.iter().find(|status| status.code == code)
+}
+
+/// Statuses whose code or reason phrase contains `partial`, for autocomplete.
+pub fn search(partial: &str) -> impl Iterator<Item = &'static HttpStatus> {
+    let partial = partial.trim().to_lowercase();
+    STATUSES.iter().filter(move |status| {
+        status.code.to_string().starts_with(&partial)
+            || status.reason.to_lowercase().contains(&partial)
+    })
+}
@@ -14,59 +14,155 @@
 #![allow(dead_code, non_snake_case)]
 
 mod commands;
-mod secrets;
+mod config;
+mod cooldown;
+pub mod error;
+mod figlet;
+pub mod http;
+mod http_status;
+mod prefix;
+pub mod providers;
+mod recent;
+mod storage;
+mod transform;
 use commands::*;
+pub use config::Config;
 use poise::serenity_prelude as serenity;
-use secrets::*;
-use shuttle_service::ShuttlePoise;
+use std::sync::Arc;
 
-pub struct Data {}
+pub struct Data {
+    pub config: Config,
+    pub cooldowns: cooldown::Cooldowns,
+    pub galleries: Arc<gallery::Sessions>,
+    pub http: reqwest::Client,
+    pub prefetch: Arc<providers::Prefetch>,
+    pub prefixes: prefix::PrefixCache,
+    pub providers: providers::ProviderRegistry,
+    pub recent: recent::RecentImages,
+    pub storage: storage::Storage,
+}
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Context<'a> = poise::Context<'a, Data, Error>;
 
-#[shuttle_service::main]
-async fn poise() -> ShuttlePoise<Data, Error> {
-    let framework = poise::Framework::builder()
-        .options(poise::FrameworkOptions {
-            prefix_options: poise::PrefixFrameworkOptions {
-                prefix: Some("fl.".into()),
-                case_insensitive_commands: true,
-                ..Default::default()
-            },
-            on_error: |err| {
-                Box::pin(async move {
-                    match err {
-                        poise::FrameworkError::Command { ctx, .. } => {
-                            println!(
-                                "An error occured: {:?}",
-                                ctx.invocation_data::<&str>().await.as_deref()
-                            );
-                        }
-                        err => poise::builtins::on_error(err).await.unwrap(),
-                    }
-                })
-            },
-            commands: vec![
-                images::animals(),
-                images::anime(),
-                info::userinfo(),
-                fun::fun(),
-            ],
+/// Commands and dispatch settings, without anything that talks to Discord.
+/// The framework and the command tests both start from this.
+pub fn options(config: &Config) -> Result<poise::FrameworkOptions<Data, Error>, Error> {
+    let mut commands = vec![
+        images::animals(),
+        images::anime(),
+        info::userinfo(),
+        subcommands_server::serverinfo(),
+        subcommands_server::roleinfo(),
+        fun::fun(),
+        subcommands_text::transform_message(),
+        subcommands_text::mock_message(),
+        subcommands_fun::uwuify_message(),
+        subcommands_fun::ascii_message(),
+        subcommands_info::avatar_user(),
+        subcommands_info::userinfo_user(),
+        favourites::favourites(),
+        settings::config(),
+        help::help(),
+    ];
+    config.filter_commands(&mut commands)?;
+
+    Ok(poise::FrameworkOptions {
+        prefix_options: poise::PrefixFrameworkOptions {
+            dynamic_prefix: Some(|ctx| Box::pin(prefix::dynamic_prefix(ctx))),
+            case_insensitive_commands: true,
             ..Default::default()
-        })
-        .token(DISCORD_TOKEN)
+        },
+        on_error: |err| Box::pin(error::on_error(err)),
+        command_check: Some(|ctx| Box::pin(cooldown::command_check(ctx))),
+        pre_command: |ctx| Box::pin(cooldown::pre_command(ctx)),
+        event_handler: |ctx, event, _framework, data| {
+            Box::pin(async move {
+                if let poise::Event::InteractionCreate {
+                    interaction: serenity::Interaction::MessageComponent(mci),
+                } = event
+                {
+                    gallery::handle_component(ctx, data, mci).await?;
+                }
+                Ok(())
+            })
+        },
+        post_command: |ctx| {
+            Box::pin(async move {
+                let usage = ctx.data().storage.usage();
+                if let Err(err) = usage
+                    .record(
+                        &ctx.command().qualified_name,
+                        ctx.author().id.0,
+                        ctx.guild_id().map(|id| id.0),
+                    )
+                    .await
+                {
+                    tracing::warn!("Failed to record command usage: {}", err);
+                }
+            })
+        },
+        owners: config
+            .owners
+            .iter()
+            .copied()
+            .map(serenity::UserId)
+            .collect(),
+        commands,
+        ..Default::default()
+    })
+}
+
+/// Opens the database and sets up the state every command runs with.
+pub async fn data(config: Config, http: Arc<serenity::Http>) -> Result<Data, Error> {
+    let storage = storage::Storage::connect(&config.database_url).await?;
+    let galleries = Arc::new(gallery::Sessions::default());
+    tokio::spawn(gallery::expire_sessions(http, galleries.clone()));
+    let client = http::client(&config.http_options())?;
+    let prefetch =
+        providers::Prefetch::new(client.clone(), config.prefetch.size, config.prefetch_ttl());
+    Ok(Data {
+        cooldowns: cooldown::Cooldowns::new(config.cooldowns.clone()),
+        galleries,
+        http: client,
+        prefetch: Arc::new(prefetch),
+        prefixes: prefix::PrefixCache::default(),
+        providers: providers::default_registry(&config),
+        recent: recent::RecentImages::new(config.duplicates.window, config.duplicates_ttl()),
+        storage,
+        config,
+    })
+}
+
+/// Builds the framework shared by the shuttle entry point and the standalone
+/// binary. Nothing connects to Discord until the framework is started.
+pub async fn framework(config: Config) -> Result<Arc<poise::Framework<Data, Error>>, Error> {
+    let token = config.discord_token.clone();
+
+    let framework = poise::Framework::builder()
+        .options(options(&config)?)
+        .token(token)
         .intents(
             serenity::GatewayIntents::non_privileged() | serenity::GatewayIntents::MESSAGE_CONTENT,
         )
-        .setup(|ctx, _ready, framework| {
+        .setup(move |ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
-                Ok(Data {})
+                data(config, ctx.http.clone()).await
             })
         })
         .build()
-        .await
-        .map_err(shuttle_service::error::CustomError::new)?;
+        .await?;
+
+    Ok(framework)
+}
+
+#[cfg(feature = "shuttle")]
+#[shuttle_service::main]
+async fn poise() -> shuttle_service::ShuttlePoise<Data, Error> {
+    use shuttle_service::error::CustomError;
+
+    let config = Config::load().map_err(CustomError::new)?;
+    let framework = framework(config).await.map_err(CustomError::msg)?;
 
     Ok(framework)
 }
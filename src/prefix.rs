@@ -0,0 +1,76 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Data, Error};
+use poise::serenity_prelude::GuildId;
+use std::{collections::HashMap, sync::RwLock};
+
+/// Guild prefixes as stored in the database, `None` meaning the guild uses
+/// the default. Every prefix command needs this lookup, so it is cached
+/// instead of hitting SQLite for each message.
+#[derive(Default)]
+pub struct PrefixCache {
+    prefixes: RwLock<HashMap<GuildId, Option<String>>>,
+}
+
+impl PrefixCache {
+    pub async fn get(&self, data: &Data, guild_id: GuildId) -> Result<Option<String>, Error> {
+        let cached = self.prefixes.read().unwrap().get(&guild_id).cloned();
+        if let Some(prefix) = cached {
+            return Ok(prefix);
+        }
+        let prefix = data.storage.guilds().get(guild_id.0).await?.prefix;
+        self.set(guild_id, prefix.clone());
+        Ok(prefix)
+    }
+
+    pub fn set(&self, guild_id: GuildId, prefix: Option<String>) {
+        self.prefixes.write().unwrap().insert(guild_id, prefix);
+    }
+}
+
+pub async fn dynamic_prefix(
+    ctx: poise::PartialContext<'_, Data, Error>,
+) -> Result<Option<String>, Error> {
+    let data = ctx.framework.user_data;
+    let prefix = match ctx.guild_id {
+        Some(guild_id) => data.prefixes.get(data, guild_id).await?,
+        None => None,
+    };
+    Ok(Some(prefix.unwrap_or_else(|| data.config.prefix.clone())))
+}
+
+/// Takes a `name=value` (or `name:value`) word out of a prefix command's
+/// text. Prefix options have to be marked like this, anything unmarked is
+/// part of the text, so `fl.fun ascii 42` draws "42".
+pub fn take_option(text: &mut String, name: &str) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let index = words
+        .iter()
+        .position(|word| option_value(word, name).is_some())?;
+    let value = option_value(words[index], name)?.to_string();
+    *text = words
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, word)| *word)
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(value)
+}
+
+fn option_value<'a>(word: &'a str, name: &str) -> Option<&'a str> {
+    let (key, value) = word.split_once(['=', ':'])?;
+    (key.eq_ignore_ascii_case(name) && !value.is_empty()).then_some(value)
+}
@@ -0,0 +1,542 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    config::{Config, RateLimit},
+    error::BotError,
+    http::get_json,
+    Error,
+};
+use poise::async_trait;
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc};
+
+mod animalapi;
+mod failover;
+mod prefetch;
+mod ratelimit;
+
+pub use animalapi::{BreedSearch, TheAnimalApi};
+pub use failover::Failover;
+pub use prefetch::Prefetch;
+pub use ratelimit::RateLimited;
+
+/// A single image fetched from a provider, plus any extra embed fields worth showing.
+#[derive(Clone)]
+pub struct ImageResult {
+    pub url: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl ImageResult {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn field(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.fields.push((name.into(), value.to_string()));
+        self
+    }
+}
+
+/// A source of random images. `name` is the registry key, `title` is what the
+/// embed and the reroll button call the animal ("Random Cat", "New Cat").
+#[async_trait]
+pub trait ImageProvider: Send + Sync {
+    fn name(&self) -> &str;
+    fn title(&self) -> &str;
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error>;
+
+    /// Fetches an image for the prefetch buffer. Rate limited providers only
+    /// spend tokens on this while they have plenty, so prefetching doesn't
+    /// use up what commands need.
+    async fn fetch_ahead(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        self.fetch(http).await
+    }
+
+    /// Breeds this provider can be narrowed down to, as (name, id) pairs.
+    async fn breeds(&self, _http: &reqwest::Client) -> Result<Vec<(String, String)>, Error> {
+        Ok(Vec::new())
+    }
+
+    /// A provider that only returns the given breed, registered under
+    /// `"{name}:{breed_id}"`.
+    fn with_breed(&self, _breed_id: &str) -> Option<Arc<dyn ImageProvider>> {
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct ProviderRegistry {
+    providers: HashMap<String, Arc<dyn ImageProvider>>,
+}
+
+impl ProviderRegistry {
+    pub fn register(&mut self, provider: impl ImageProvider + 'static) -> &mut Self {
+        self.providers
+            .insert(provider.name().to_string(), Arc::new(provider));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn ImageProvider>> {
+        match name.split_once(':') {
+            Some((base, breed_id)) => self.providers.get(base)?.with_breed(breed_id),
+            None => self.providers.get(name).cloned(),
+        }
+    }
+
+    /// Whether a provider is registered under exactly `name`, breeds aside.
+    pub fn contains(&self, name: &str) -> bool {
+        self.providers.contains_key(name)
+    }
+
+    /// Puts an already registered provider behind a rate limit. Failover
+    /// groups registered before this keep using the unlimited provider.
+    pub fn rate_limit(&mut self, name: &str, limit: RateLimit) -> &mut Self {
+        match self.providers.get(name).cloned() {
+            Some(provider) => self.register(RateLimited::new(provider, limit)),
+            None => {
+                tracing::warn!("Not rate limiting {}, there's no such image provider", name);
+                self
+            }
+        }
+    }
+
+    /// Registers a failover group over already registered providers, tried in
+    /// the order given.
+    pub fn failover(&mut self, name: &str, title: &str, members: &[&str]) -> &mut Self {
+        let providers = members
+            .iter()
+            .filter_map(|member| self.get(member))
+            .collect();
+        self.register(Failover::new(name, title, providers))
+    }
+}
+
+fn no_images(service: &str) -> BotError {
+    BotError::Decode {
+        service: service.to_string(),
+        message: String::from("response contained no images"),
+    }
+}
+
+/// Every image API on its own, without rate limits or failover groups.
+pub fn base_registry(config: &Config) -> ProviderRegistry {
+    let endpoints = &config.endpoints;
+    let mut registry = ProviderRegistry::default();
+    registry
+        .register(TheAnimalApi::cat(
+            &endpoints.thecatapi,
+            &config.api_keys.cat,
+        ))
+        .register(TheAnimalApi::dog(
+            &endpoints.thedogapi,
+            &config.api_keys.dog,
+        ))
+        .register(RandomFox::new(&endpoints.randomfox))
+        .register(ShibeOnline::new(ShibeOnlineKind::Shibe, &endpoints.shibe))
+        .register(ShibeOnline::new(ShibeOnlineKind::Bird, &endpoints.shibe))
+        .register(ShibeOnline::new(ShibeOnlineKind::Cat, &endpoints.shibe))
+        .register(AwsRandomCat::new(&endpoints.awscat))
+        .register(RandomDuck::new(&endpoints.randomduck))
+        .register(RandomDog::new(&endpoints.randomdog))
+        .register(NekosBest::new(&endpoints.nekosbest))
+        .register(WaifuPics::neko(&endpoints.waifupics))
+        .register(Purrbot::new(PurrbotKind::Neko, &endpoints.purrbot))
+        .register(Purrbot::new(PurrbotKind::Okami, &endpoints.purrbot))
+        .register(Purrbot::new(PurrbotKind::Kitsune, &endpoints.purrbot));
+    registry
+}
+
+pub fn default_registry(config: &Config) -> ProviderRegistry {
+    let mut registry = base_registry(config);
+    for (name, limit) in &config.rate_limits {
+        registry.rate_limit(name, *limit);
+    }
+    registry
+        .failover("cat", "Cat", &["thecatapi", "awscat", "shibe-cats"])
+        .failover("dog", "Dog", &["thedogapi", "randomdog"])
+        .failover(
+            "neko",
+            "Neko",
+            &["nekosbest-neko", "waifupics-neko", "purrbot-neko"],
+        );
+    registry
+}
+
+#[derive(Deserialize)]
+struct RandomFoxResponse {
+    image: String,
+    link: String,
+}
+
+#[derive(Deserialize)]
+struct AWSRandomCat {
+    file: String,
+}
+
+#[derive(Deserialize)]
+struct RandomDogResponse {
+    #[serde(rename = "fileSizeBytes")]
+    file_size_bytes: i128,
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct RandomDuckResponse {
+    url: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct PurrbotImage {
+    error: bool,
+    link: String,
+    time: i16,
+}
+
+#[derive(Deserialize)]
+struct NekosBestImage {
+    url: String,
+    artist_name: Option<String>,
+    artist_href: Option<String>,
+    source_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NekosBestResponse {
+    results: Vec<NekosBestImage>,
+}
+
+#[derive(Deserialize)]
+struct WaifuPicsResponse {
+    url: String,
+}
+
+// randomfox.ca
+
+pub struct RandomFox {
+    base_url: String,
+}
+
+impl RandomFox {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for RandomFox {
+    fn name(&self) -> &str {
+        "randomfox"
+    }
+
+    fn title(&self) -> &str {
+        "Fox"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let parsed: RandomFoxResponse =
+            get_json(http, &format!("{}/floof/", self.base_url)).await?;
+        Ok(ImageResult::new(parsed.image))
+    }
+}
+
+// shibe.online
+
+pub enum ShibeOnlineKind {
+    Bird,
+    Shibe,
+    Cat,
+}
+
+impl ShibeOnlineKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ShibeOnlineKind::Bird => "birds",
+            ShibeOnlineKind::Shibe => "shibes",
+            ShibeOnlineKind::Cat => "cats",
+        }
+    }
+}
+
+pub struct ShibeOnline {
+    kind: ShibeOnlineKind,
+    name: String,
+    base_url: String,
+}
+
+impl ShibeOnline {
+    pub fn new(kind: ShibeOnlineKind, base_url: impl Into<String>) -> Self {
+        Self {
+            name: format!("shibe-{}", kind.as_str()),
+            kind,
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for ShibeOnline {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn title(&self) -> &str {
+        match self.kind {
+            ShibeOnlineKind::Bird => "Bird",
+            ShibeOnlineKind::Shibe => "Shiba",
+            ShibeOnlineKind::Cat => "Cat",
+        }
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let url = format!("{}/api/{}", self.base_url, self.kind.as_str());
+        let parsed: Vec<String> = get_json(http, &url).await?;
+        let image = parsed
+            .into_iter()
+            .next()
+            .ok_or_else(|| no_images("shibe.online"))?;
+        Ok(ImageResult::new(image))
+    }
+}
+
+// aws.random.cat
+
+pub struct AwsRandomCat {
+    base_url: String,
+}
+
+impl AwsRandomCat {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for AwsRandomCat {
+    fn name(&self) -> &str {
+        "awscat"
+    }
+
+    fn title(&self) -> &str {
+        "Cat"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let parsed: AWSRandomCat = get_json(http, &format!("{}/meow", self.base_url)).await?;
+        Ok(ImageResult::new(parsed.file))
+    }
+}
+
+// random.dog
+
+pub struct RandomDog {
+    base_url: String,
+}
+
+impl RandomDog {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for RandomDog {
+    fn name(&self) -> &str {
+        "randomdog"
+    }
+
+    fn title(&self) -> &str {
+        "Dog"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let parsed: RandomDogResponse =
+            get_json(http, &format!("{}/woof.json", self.base_url)).await?;
+        Ok(ImageResult::new(parsed.url))
+    }
+}
+
+// random-d.uk
+
+pub struct RandomDuck {
+    base_url: String,
+}
+
+impl RandomDuck {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for RandomDuck {
+    fn name(&self) -> &str {
+        "randomduck"
+    }
+
+    fn title(&self) -> &str {
+        "Duck"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let parsed: RandomDuckResponse =
+            get_json(http, &format!("{}/api/v2/random", self.base_url)).await?;
+        Ok(ImageResult::new(parsed.url))
+    }
+}
+
+// nekos.best
+
+pub struct NekosBest {
+    base_url: String,
+}
+
+impl NekosBest {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for NekosBest {
+    fn name(&self) -> &str {
+        "nekosbest-neko"
+    }
+
+    fn title(&self) -> &str {
+        "Neko"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let parsed: NekosBestResponse =
+            get_json(http, &format!("{}/api/v2/neko", self.base_url)).await?;
+        let image = parsed
+            .results
+            .into_iter()
+            .next()
+            .ok_or_else(|| no_images("nekos.best"))?;
+        let mut result = ImageResult::new(image.url);
+        if let Some(artist) = image.artist_name {
+            result = result.field("Artist", artist);
+        }
+        Ok(result)
+    }
+}
+
+// waifu.pics
+
+pub struct WaifuPics {
+    name: &'static str,
+    title: &'static str,
+    category: &'static str,
+    base_url: String,
+}
+
+impl WaifuPics {
+    pub fn neko(base_url: impl Into<String>) -> Self {
+        Self {
+            name: "waifupics-neko",
+            title: "Neko",
+            category: "neko",
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for WaifuPics {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn title(&self) -> &str {
+        self.title
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let url = format!("{}/sfw/{}", self.base_url, self.category);
+        let parsed: WaifuPicsResponse = get_json(http, &url).await?;
+        Ok(ImageResult::new(parsed.url))
+    }
+}
+
+// purrbot.site
+
+pub enum PurrbotKind {
+    Kitsune,
+    Neko,
+    Okami,
+}
+
+impl PurrbotKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PurrbotKind::Kitsune => "kitsune",
+            PurrbotKind::Okami => "okami",
+            PurrbotKind::Neko => "neko",
+        }
+    }
+}
+
+pub struct Purrbot {
+    kind: PurrbotKind,
+    name: String,
+    base_url: String,
+}
+
+impl Purrbot {
+    pub fn new(kind: PurrbotKind, base_url: impl Into<String>) -> Self {
+        Self {
+            name: format!("purrbot-{}", kind.as_str()),
+            kind,
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for Purrbot {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn title(&self) -> &str {
+        match self.kind {
+            PurrbotKind::Kitsune => "Kitsune",
+            PurrbotKind::Neko => "Neko",
+            PurrbotKind::Okami => "Okami",
+        }
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let url = format!("{}/api/img/sfw/{}/img", self.base_url, self.kind.as_str());
+        let parsed: PurrbotImage = get_json(http, &url).await?;
+        Ok(ImageResult::new(parsed.link).field("API Response Time", parsed.time))
+    }
+}
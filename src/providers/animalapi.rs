@@ -0,0 +1,212 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{no_images, ImageProvider, ImageResult};
+use crate::{http::send_json, Error};
+use poise::async_trait;
+use serde::Deserialize;
+use std::sync::{Arc, RwLock};
+
+/// (name, id) pairs, as `ImageProvider::breeds` returns them.
+type BreedList = Vec<(String, String)>;
+
+#[derive(Deserialize)]
+struct Weight {
+    imperial: Option<String>,
+    metric: Option<String>,
+}
+
+/// TheCatAPI breed ids are strings ("beng"), TheDogAPI's are numbers.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BreedId {
+    Text(String),
+    Number(u64),
+}
+
+impl BreedId {
+    fn into_string(self) -> String {
+        match self {
+            BreedId::Text(id) => id,
+            BreedId::Number(id) => id.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Breeds {
+    weight: Option<Weight>,
+    id: Option<BreedId>,
+    name: Option<String>,
+    temperament: Option<String>,
+    origin: Option<String>,
+    country_codes: Option<String>,
+    country_code: Option<String>,
+    life_span: Option<String>,
+    wikipedia_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AnimalAPIResponse {
+    id: String,
+    width: i16,
+    height: i16,
+    url: String,
+    breeds: Option<Vec<Breeds>>,
+}
+
+fn breed_fields(mut image: ImageResult, breed: Breeds) -> ImageResult {
+    if let Some(name) = breed.name {
+        image = image.field("Breed", name);
+    }
+    if let Some(temperament) = breed.temperament {
+        image = image.field("Temperament", temperament);
+    }
+    if let Some(origin) = breed.origin {
+        image = image.field("Origin", origin);
+    }
+    if let Some(life_span) = breed.life_span {
+        // TheDogAPI already says "years", TheCatAPI doesn't.
+        let life_span = life_span.trim();
+        if life_span.ends_with("years") {
+            image = image.field("Lifespan", life_span);
+        } else {
+            image = image.field("Lifespan", format!("{} years", life_span));
+        }
+    }
+    if let Some(metric) = breed.weight.and_then(|weight| weight.metric) {
+        image = image.field("Weight", format!("{} kg", metric));
+    }
+    if let Some(wikipedia_url) = breed.wikipedia_url {
+        image = image.field("Wikipedia", format!("[Read more]({})", wikipedia_url));
+    }
+    image
+}
+
+/// thecatapi.com and thedogapi.com share the same API, only the host and
+/// key differ.
+#[derive(Clone)]
+pub struct TheAnimalApi {
+    name: &'static str,
+    title: &'static str,
+    base_url: String,
+    api_key: String,
+    breeds: Arc<RwLock<Option<BreedList>>>,
+}
+
+impl TheAnimalApi {
+    pub fn cat(base_url: &str, api_key: &str) -> Self {
+        Self::new("thecatapi", "Cat", base_url, api_key)
+    }
+
+    pub fn dog(base_url: &str, api_key: &str) -> Self {
+        Self::new("thedogapi", "Dog", base_url, api_key)
+    }
+
+    fn new(name: &'static str, title: &'static str, base_url: &str, api_key: &str) -> Self {
+        Self {
+            name,
+            title,
+            base_url: base_url.to_string(),
+            api_key: api_key.to_string(),
+            breeds: Arc::default(),
+        }
+    }
+
+    async fn search(
+        &self,
+        http: &reqwest::Client,
+        breed_id: Option<&str>,
+    ) -> Result<ImageResult, Error> {
+        let mut request = http
+            .get(format!("{}/images/search", self.base_url))
+            .header("x-api-key", &self.api_key);
+        if let Some(breed_id) = breed_id {
+            request = request.query(&[("breed_ids", breed_id)]);
+        }
+        let mut parsed: Vec<AnimalAPIResponse> = send_json(request).await?;
+        if parsed.is_empty() {
+            return Err(no_images(self.name).into());
+        }
+        let response = parsed.swap_remove(0);
+        let image = ImageResult::new(response.url);
+        Ok(
+            match response.breeds.and_then(|breeds| breeds.into_iter().next()) {
+                Some(breed) => breed_fields(image, breed),
+                None => image,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl ImageProvider for TheAnimalApi {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn title(&self) -> &str {
+        self.title
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        self.search(http, None).await
+    }
+
+    async fn breeds(&self, http: &reqwest::Client) -> Result<BreedList, Error> {
+        let cached = self.breeds.read().unwrap().clone();
+        if let Some(breeds) = cached {
+            return Ok(breeds);
+        }
+        let request = http
+            .get(format!("{}/breeds", self.base_url))
+            .header("x-api-key", &self.api_key);
+        let parsed: Vec<Breeds> = send_json(request).await?;
+        let breeds: BreedList = parsed
+            .into_iter()
+            .filter_map(|breed| Some((breed.name?, breed.id?.into_string())))
+            .collect();
+        *self.breeds.write().unwrap() = Some(breeds.clone());
+        Ok(breeds)
+    }
+
+    fn with_breed(&self, breed_id: &str) -> Option<Arc<dyn ImageProvider>> {
+        Some(Arc::new(BreedSearch {
+            name: format!("{}:{}", self.name, breed_id),
+            breed_id: breed_id.to_string(),
+            api: self.clone(),
+        }))
+    }
+}
+
+pub struct BreedSearch {
+    name: String,
+    breed_id: String,
+    api: TheAnimalApi,
+}
+
+#[async_trait]
+impl ImageProvider for BreedSearch {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn title(&self) -> &str {
+        self.api.title
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        self.api.search(http, Some(&self.breed_id)).await
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{ImageProvider, ImageResult};
+use crate::{error::BotError, Error};
+use poise::async_trait;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const FAILURE_THRESHOLD: u32 = 3;
+const OPEN_DURATION: Duration = Duration::from_secs(300);
+
+#[derive(Default)]
+struct Health {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl Health {
+    fn is_open(&self, now: Instant) -> bool {
+        self.open_until.is_some_and(|until| until > now)
+    }
+}
+
+/// Tries several providers of the same animal in priority order. A provider
+/// that fails `FAILURE_THRESHOLD` times in a row is skipped for
+/// `OPEN_DURATION`, unless every provider in the group is currently skipped.
+pub struct Failover {
+    name: String,
+    title: String,
+    providers: Vec<(Arc<dyn ImageProvider>, Mutex<Health>)>,
+}
+
+impl Failover {
+    pub fn new(
+        name: impl Into<String>,
+        title: impl Into<String>,
+        providers: Vec<Arc<dyn ImageProvider>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            title: title.into(),
+            providers: providers
+                .into_iter()
+                .map(|provider| (provider, Mutex::new(Health::default())))
+                .collect(),
+        }
+    }
+
+    fn record_success(&self, health: &Mutex<Health>) {
+        let mut health = health.lock().unwrap();
+        health.consecutive_failures = 0;
+        health.open_until = None;
+    }
+
+    fn record_failure(&self, provider: &dyn ImageProvider, health: &Mutex<Health>) {
+        let mut health = health.lock().unwrap();
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= FAILURE_THRESHOLD {
+            tracing::warn!(
+                "{} marked unhealthy in {} after {} failures",
+                provider.name(),
+                self.name,
+                health.consecutive_failures
+            );
+            health.open_until = Some(Instant::now() + OPEN_DURATION);
+        }
+    }
+
+    async fn fetch_from(&self, http: &reqwest::Client, ahead: bool) -> Result<ImageResult, Error> {
+        let now = Instant::now();
+        let healthy: Vec<_> = self
+            .providers
+            .iter()
+            .filter(|(_, health)| !health.lock().unwrap().is_open(now))
+            .collect();
+        let candidates = if healthy.is_empty() {
+            self.providers.iter().collect()
+        } else {
+            healthy
+        };
+
+        let mut last_error = None;
+        for (provider, health) in candidates {
+            let fetched = if ahead {
+                provider.fetch_ahead(http).await
+            } else {
+                provider.fetch(http).await
+            };
+            match fetched {
+                Ok(image) => {
+                    self.record_success(health);
+                    return Ok(image);
+                }
+                Err(err) => {
+                    let limited = matches!(
+                        err.downcast_ref::<BotError>(),
+                        Some(BotError::RateLimited(_))
+                    );
+                    // Our own rate limit says nothing about the provider's health.
+                    if !limited {
+                        tracing::warn!("{} failed in {}: {}", provider.name(), self.name, err);
+                        self.record_failure(provider.as_ref(), health);
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| format!("{} has no providers", self.name).into()))
+    }
+}
+
+#[async_trait]
+impl ImageProvider for Failover {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        self.fetch_from(http, false).await
+    }
+
+    async fn fetch_ahead(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        self.fetch_from(http, true).await
+    }
+}
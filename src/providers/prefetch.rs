@@ -0,0 +1,152 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{ImageProvider, ImageResult};
+use crate::Error;
+use reqwest::StatusCode;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Fetches a refill may make on top of filling the buffer, so a provider
+/// whose images keep failing validation isn't asked forever.
+const EXTRA_ATTEMPTS: usize = 3;
+
+#[derive(Default)]
+struct Buffer {
+    images: VecDeque<(Instant, ImageResult)>,
+    refilling: bool,
+}
+
+impl Buffer {
+    fn drop_expired(&mut self, ttl: Duration) {
+        let now = Instant::now();
+        self.images
+            .retain(|(fetched_at, _)| now.duration_since(*fetched_at) < ttl);
+    }
+}
+
+/// Keeps a few images from each provider fetched ahead of time, so rerolls
+/// don't have to wait for the upstream API. A provider's buffer starts
+/// filling in the background the first time it's used and is topped up
+/// again after every image taken from it.
+pub struct Prefetch {
+    http: reqwest::Client,
+    size: usize,
+    ttl: Duration,
+    buffers: Mutex<HashMap<String, Buffer>>,
+}
+
+impl Prefetch {
+    /// `size` 0 turns prefetching off, every image is then fetched on demand.
+    pub fn new(http: reqwest::Client, size: usize, ttl: Duration) -> Self {
+        Self {
+            http,
+            size,
+            ttl,
+            buffers: Mutex::default(),
+        }
+    }
+
+    /// A buffered image if there is one, without waiting for the provider.
+    /// Breed providers (`"thecatapi:beng"`) aren't prefetched, most breeds
+    /// are only asked for once in a while.
+    pub fn take(self: &Arc<Self>, provider: &Arc<dyn ImageProvider>) -> Option<ImageResult> {
+        if self.size == 0 || provider.name().contains(':') {
+            return None;
+        }
+        let image = {
+            let mut buffers = self.buffers.lock().unwrap();
+            let buffer = buffers.entry(provider.name().to_string()).or_default();
+            buffer.drop_expired(self.ttl);
+            buffer.images.pop_front().map(|(_, image)| image)
+        };
+        self.start_refill(provider);
+        image
+    }
+
+    /// A buffered image, or one fetched right now when the buffer is empty.
+    pub async fn fetch(
+        self: &Arc<Self>,
+        provider: &Arc<dyn ImageProvider>,
+    ) -> Result<ImageResult, Error> {
+        match self.take(provider) {
+            Some(image) => Ok(image),
+            None => provider.fetch(&self.http).await,
+        }
+    }
+
+    fn start_refill(self: &Arc<Self>, provider: &Arc<dyn ImageProvider>) {
+        {
+            let mut buffers = self.buffers.lock().unwrap();
+            let buffer = buffers.entry(provider.name().to_string()).or_default();
+            if buffer.refilling || buffer.images.len() >= self.size {
+                return;
+            }
+            buffer.refilling = true;
+        }
+        tokio::spawn(self.clone().refill(provider.clone()));
+    }
+
+    /// How many unexpired images are waiting for the named provider.
+    pub fn buffered(&self, name: &str) -> usize {
+        let mut buffers = self.buffers.lock().unwrap();
+        match buffers.get_mut(name) {
+            Some(buffer) => {
+                buffer.drop_expired(self.ttl);
+                buffer.images.len()
+            }
+            None => 0,
+        }
+    }
+
+    async fn refill(self: Arc<Self>, provider: Arc<dyn ImageProvider>) {
+        let name = provider.name().to_string();
+        for _ in 0..self.size + EXTRA_ATTEMPTS {
+            if self.buffered(&name) >= self.size {
+                break;
+            }
+            let image = match provider.fetch_ahead(&self.http).await {
+                Ok(image) => image,
+                Err(err) => {
+                    tracing::debug!("Prefetching from {} failed: {}", name, err);
+                    break;
+                }
+            };
+            if !resolves(&self.http, &image.url).await {
+                tracing::debug!("Dropping {} from {}, it doesn't resolve", image.url, name);
+                continue;
+            }
+            if let Some(buffer) = self.buffers.lock().unwrap().get_mut(&name) {
+                buffer.images.push_back((Instant::now(), image));
+            }
+        }
+        if let Some(buffer) = self.buffers.lock().unwrap().get_mut(&name) {
+            buffer.refilling = false;
+        }
+    }
+}
+
+/// Image hosts sometimes hand out links that are already dead. Hosts that
+/// don't allow HEAD requests get the benefit of the doubt.
+async fn resolves(http: &reqwest::Client, url: &str) -> bool {
+    match http.head(url).send().await {
+        Ok(response) => {
+            response.status().is_success() || response.status() == StatusCode::METHOD_NOT_ALLOWED
+        }
+        Err(_) => false,
+    }
+}
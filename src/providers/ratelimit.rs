@@ -0,0 +1,116 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{ImageProvider, ImageResult};
+use crate::{config::RateLimit, error::BotError, Error};
+use poise::async_trait;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+struct TokenBucket {
+    capacity: f64,
+    per_sec: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            capacity: f64::from(limit.burst),
+            per_sec: f64::from(limit.per_minute) / 60.0,
+            tokens: f64::from(limit.burst),
+            updated: Instant::now(),
+        }
+    }
+
+    /// Takes a token, or says how long until the next one.
+    fn take(&mut self) -> Result<(), Duration> {
+        self.take_above(0.0)
+    }
+
+    /// Takes a token only if `reserve` tokens are left afterwards.
+    fn take_above(&mut self, reserve: f64) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+        if self.tokens >= reserve + 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (reserve + 1.0 - self.tokens) / self.per_sec,
+            ))
+        }
+    }
+}
+
+/// Wraps a provider so it makes at most as many requests as its bucket
+/// allows, whichever command or button asks. Breed searches share the bucket
+/// of the provider they came from, since they hit the same API key.
+pub struct RateLimited {
+    provider: Arc<dyn ImageProvider>,
+    bucket: Arc<Mutex<TokenBucket>>,
+}
+
+impl RateLimited {
+    pub fn new(provider: Arc<dyn ImageProvider>, limit: RateLimit) -> Self {
+        Self {
+            provider,
+            bucket: Arc::new(Mutex::new(TokenBucket::new(limit))),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for RateLimited {
+    fn name(&self) -> &str {
+        self.provider.name()
+    }
+
+    fn title(&self) -> &str {
+        self.provider.title()
+    }
+
+    async fn fetch(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let taken = self.bucket.lock().unwrap().take();
+        taken.map_err(BotError::RateLimited)?;
+        self.provider.fetch(http).await
+    }
+
+    /// Leaves half the burst for commands.
+    async fn fetch_ahead(&self, http: &reqwest::Client) -> Result<ImageResult, Error> {
+        let taken = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let reserve = bucket.capacity / 2.0;
+            bucket.take_above(reserve)
+        };
+        taken.map_err(BotError::RateLimited)?;
+        self.provider.fetch_ahead(http).await
+    }
+
+    async fn breeds(&self, http: &reqwest::Client) -> Result<Vec<(String, String)>, Error> {
+        self.provider.breeds(http).await
+    }
+
+    fn with_breed(&self, breed_id: &str) -> Option<Arc<dyn ImageProvider>> {
+        Some(Arc::new(Self {
+            provider: self.provider.with_breed(breed_id)?,
+            bucket: self.bucket.clone(),
+        }))
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::serenity_prelude::ChannelId;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Once this many channels are remembered, the ones with nothing left
+/// unexpired are forgotten.
+const MAX_CHANNELS: usize = 10_000;
+
+/// The images each channel was shown lately, so image commands can ask for
+/// another one instead of repeating a picture. Each channel keeps its last
+/// `window` images, each for at most `ttl`.
+pub struct RecentImages {
+    window: usize,
+    ttl: Duration,
+    channels: Mutex<HashMap<ChannelId, VecDeque<(Instant, String)>>>,
+}
+
+impl RecentImages {
+    /// `window` 0 turns this off, nothing then counts as seen.
+    pub fn new(window: usize, ttl: Duration) -> Self {
+        Self {
+            window,
+            ttl,
+            channels: Mutex::default(),
+        }
+    }
+
+    pub fn contains(&self, channel: ChannelId, url: &str) -> bool {
+        let mut channels = self.channels.lock().unwrap();
+        match channels.get_mut(&channel) {
+            Some(seen) => {
+                drop_expired(seen, self.ttl);
+                seen.iter().any(|(_, seen)| seen == url)
+            }
+            None => false,
+        }
+    }
+
+    pub fn insert(&self, channel: ChannelId, url: &str) {
+        if self.window == 0 {
+            return;
+        }
+        let mut channels = self.channels.lock().unwrap();
+        if channels.len() >= MAX_CHANNELS && !channels.contains_key(&channel) {
+            channels.retain(|_, seen| {
+                drop_expired(seen, self.ttl);
+                !seen.is_empty()
+            });
+        }
+        let seen = channels.entry(channel).or_default();
+        drop_expired(seen, self.ttl);
+        seen.retain(|(_, seen)| seen != url);
+        seen.push_back((Instant::now(), url.to_string()));
+        while seen.len() > self.window {
+            seen.pop_front();
+        }
+    }
+}
+
+fn drop_expired(seen: &mut VecDeque<(Instant, String)>, ttl: Duration) {
+    while let Some((shown_at, _)) = seen.front() {
+        if shown_at.elapsed() < ttl {
+            break;
+        }
+        seen.pop_front();
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool,
+};
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+mod favourites;
+mod guilds;
+mod usage;
+mod users;
+
+pub use favourites::{Favourite, FavouritesRepository};
+pub use guilds::GuildSettingsRepository;
+pub use usage::CommandUsageRepository;
+pub use users::UserPreferencesRepository;
+
+/// SQLite handle shared through `Data`. The repositories are cheap views
+/// over the same connection pool.
+#[derive(Clone)]
+pub struct Storage {
+    pool: SqlitePool,
+}
+
+impl Storage {
+    /// Opens (creating if needed) the database and applies any pending
+    /// migrations from `migrations/`, which are embedded at compile time.
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await?;
+        sqlx::migrate!().run(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    pub fn guilds(&self) -> GuildSettingsRepository<'_> {
+        GuildSettingsRepository { pool: &self.pool }
+    }
+
+    pub fn users(&self) -> UserPreferencesRepository<'_> {
+        UserPreferencesRepository { pool: &self.pool }
+    }
+
+    pub fn favourites(&self) -> FavouritesRepository<'_> {
+        FavouritesRepository { pool: &self.pool }
+    }
+
+    pub fn usage(&self) -> CommandUsageRepository<'_> {
+        CommandUsageRepository { pool: &self.pool }
+    }
+}
+
+// SQLite only has signed 64-bit integers, Discord ids fit since they are
+// well below 2^63.
+fn to_db_id(id: u64) -> i64 {
+    id as i64
+}
+
+fn from_db_id(id: i64) -> u64 {
+    id as u64
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as i64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,84 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{to_db_id, unix_now};
+use sqlx::SqlitePool;
+
+#[derive(sqlx::FromRow)]
+pub struct Favourite {
+    pub id: i64,
+    pub url: String,
+    pub provider: String,
+    pub saved_at: i64,
+}
+
+/// Images users saved with the ⭐ button on image embeds.
+pub struct FavouritesRepository<'a> {
+    pub(super) pool: &'a SqlitePool,
+}
+
+impl FavouritesRepository<'_> {
+    /// Returns `false` if the user already saved this URL.
+    pub async fn add(&self, user_id: u64, url: &str, provider: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO favourites (user_id, url, provider, saved_at)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(to_db_id(user_id))
+        .bind(url)
+        .bind(provider)
+        .bind(unix_now())
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Newest first.
+    pub async fn list(&self, user_id: u64) -> Result<Vec<Favourite>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, url, provider, saved_at FROM favourites
+             WHERE user_id = ? ORDER BY saved_at DESC, id DESC",
+        )
+        .bind(to_db_id(user_id))
+        .fetch_all(self.pool)
+        .await
+    }
+
+    pub async fn random(&self, user_id: u64) -> Result<Option<Favourite>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, url, provider, saved_at FROM favourites
+             WHERE user_id = ? ORDER BY RANDOM() LIMIT 1",
+        )
+        .bind(to_db_id(user_id))
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    pub async fn count(&self, user_id: u64) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM favourites WHERE user_id = ?")
+            .bind(to_db_id(user_id))
+            .fetch_one(self.pool)
+            .await
+    }
+
+    /// Returns `false` if the user has no favourite with that id.
+    pub async fn remove(&self, user_id: u64, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM favourites WHERE user_id = ? AND id = ?")
+            .bind(to_db_id(user_id))
+            .bind(id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
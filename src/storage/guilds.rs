@@ -0,0 +1,87 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{from_db_id, to_db_id};
+use sqlx::SqlitePool;
+
+#[derive(sqlx::FromRow)]
+struct GuildSettingsRow {
+    guild_id: i64,
+    prefix: Option<String>,
+    shared_buttons: bool,
+}
+
+#[derive(Clone)]
+pub struct GuildSettings {
+    pub guild_id: u64,
+    pub prefix: Option<String>,
+    /// Whether anyone, not just whoever ran the command, can use the buttons
+    /// on image embeds.
+    pub shared_buttons: bool,
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        Self {
+            guild_id: 0,
+            prefix: None,
+            shared_buttons: true,
+        }
+    }
+}
+
+impl From<GuildSettingsRow> for GuildSettings {
+    fn from(row: GuildSettingsRow) -> Self {
+        Self {
+            guild_id: from_db_id(row.guild_id),
+            prefix: row.prefix,
+            shared_buttons: row.shared_buttons,
+        }
+    }
+}
+
+pub struct GuildSettingsRepository<'a> {
+    pub(super) pool: &'a SqlitePool,
+}
+
+impl GuildSettingsRepository<'_> {
+    /// Returns the stored settings, or the defaults if the guild never
+    /// changed anything.
+    pub async fn get(&self, guild_id: u64) -> Result<GuildSettings, sqlx::Error> {
+        let row: Option<GuildSettingsRow> = sqlx::query_as(
+            "SELECT guild_id, prefix, shared_buttons FROM guild_settings WHERE guild_id = ?",
+        )
+        .bind(to_db_id(guild_id))
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(row.map(GuildSettings::from).unwrap_or(GuildSettings {
+            guild_id,
+            ..GuildSettings::default()
+        }))
+    }
+
+    pub async fn save(&self, settings: &GuildSettings) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO guild_settings (guild_id, prefix, shared_buttons) VALUES (?, ?, ?)
+             ON CONFLICT (guild_id) DO UPDATE
+             SET prefix = excluded.prefix, shared_buttons = excluded.shared_buttons",
+        )
+        .bind(to_db_id(settings.guild_id))
+        .bind(&settings.prefix)
+        .bind(settings.shared_buttons)
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{to_db_id, unix_now};
+use sqlx::SqlitePool;
+
+#[derive(sqlx::FromRow)]
+pub struct UsageCount {
+    pub command: String,
+    pub uses: i64,
+}
+
+pub struct CommandUsageRepository<'a> {
+    pub(super) pool: &'a SqlitePool,
+}
+
+impl CommandUsageRepository<'_> {
+    pub async fn record(
+        &self,
+        command: &str,
+        user_id: u64,
+        guild_id: Option<u64>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO command_usage (command, user_id, guild_id, used_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(command)
+        .bind(to_db_id(user_id))
+        .bind(guild_id.map(to_db_id))
+        .bind(unix_now())
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Most used commands, optionally limited to one guild.
+    pub async fn top(
+        &self,
+        guild_id: Option<u64>,
+        limit: u32,
+    ) -> Result<Vec<UsageCount>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT command, COUNT(*) AS uses FROM command_usage
+             WHERE ?1 IS NULL OR guild_id = ?1
+             GROUP BY command ORDER BY uses DESC LIMIT ?2",
+        )
+        .bind(guild_id.map(to_db_id))
+        .bind(limit)
+        .fetch_all(self.pool)
+        .await
+    }
+}
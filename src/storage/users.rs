@@ -0,0 +1,54 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::to_db_id;
+use sqlx::SqlitePool;
+
+/// Free-form per-user settings, stored as key/value pairs so new
+/// preferences don't need a migration each.
+pub struct UserPreferencesRepository<'a> {
+    pub(super) pool: &'a SqlitePool,
+}
+
+impl UserPreferencesRepository<'_> {
+    pub async fn get(&self, user_id: u64, key: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT value FROM user_preferences WHERE user_id = ? AND key = ?")
+            .bind(to_db_id(user_id))
+            .bind(key)
+            .fetch_optional(self.pool)
+            .await
+    }
+
+    pub async fn set(&self, user_id: u64, key: &str, value: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO user_preferences (user_id, key, value) VALUES (?, ?, ?)
+             ON CONFLICT (user_id, key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(to_db_id(user_id))
+        .bind(key)
+        .bind(value)
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, user_id: u64, key: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM user_preferences WHERE user_id = ? AND key = ?")
+            .bind(to_db_id(user_id))
+            .bind(key)
+            .execute(self.pool)
+            .await?;
+        Ok(())
+    }
+}
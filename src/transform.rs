@@ -0,0 +1,279 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::{seq::SliceRandom, Rng};
+
+/// Discord's limit on message length.
+pub const MESSAGE_LIMIT: usize = 2000;
+
+#[derive(Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum OwoStyle {
+    #[name = "OwO"]
+    Owo,
+    #[name = "UwU"]
+    Uwu,
+    #[name = "nya~"]
+    Nya,
+}
+
+#[derive(Clone, Copy)]
+pub enum Transform {
+    Mock,
+    Leet,
+    /// Number of combining marks stacked on each character, at most.
+    Zalgo(u8),
+    Reverse,
+    UpsideDown,
+    Vaporwave,
+    SmallCaps,
+    RegionalIndicators,
+    Owo(OwoStyle),
+    Uwuify,
+}
+
+/// What the "Transform text" context menu offers, as (label, transform).
+pub static MENU: &[(&str, Transform)] = &[
+    ("SpOnGeBoB mock", Transform::Mock),
+    ("1337 5p34k", Transform::Leet),
+    ("Z̷a̴l̶g̸o̵", Transform::Zalgo(3)),
+    ("Reversed", Transform::Reverse),
+    ("uʍop ǝpᴉsd∩", Transform::UpsideDown),
+    ("Ｖａｐｏｒｗａｖｅ", Transform::Vaporwave),
+    ("Sᴍᴀʟʟ ᴄᴀᴘs", Transform::SmallCaps),
+    ("Regional indicators", Transform::RegionalIndicators),
+    ("OwO", Transform::Owo(OwoStyle::Owo)),
+    ("UwU", Transform::Owo(OwoStyle::Uwu)),
+    ("nya~", Transform::Owo(OwoStyle::Nya)),
+];
+
+impl Transform {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Transform::Mock => mock(text),
+            Transform::Leet => text.chars().map(leet).collect(),
+            Transform::Zalgo(intensity) => zalgo(text, intensity),
+            Transform::Reverse => text.chars().rev().collect(),
+            Transform::UpsideDown => text.chars().rev().map(upside_down).collect(),
+            Transform::Vaporwave => text.chars().map(full_width).collect(),
+            Transform::SmallCaps => text.chars().map(small_cap).collect(),
+            Transform::RegionalIndicators => regional_indicators(text),
+            Transform::Owo(style) => owo(text, style),
+            Transform::Uwuify => uwuifier::uwuify_str_sse(text),
+        }
+    }
+}
+
+/// Cuts `text` down to what fits in a message.
+pub fn fit(mut text: String) -> String {
+    if text.chars().count() > MESSAGE_LIMIT {
+        if let Some((cut, _)) = text.char_indices().nth(MESSAGE_LIMIT - 1) {
+            text.truncate(cut);
+            text.push('…');
+        }
+    }
+    text
+}
+
+fn mock(text: &str) -> String {
+    let mut upper = false;
+    text.chars()
+        .map(|c| {
+            if !c.is_alphabetic() {
+                return c.to_string();
+            }
+            let mocked = if upper {
+                c.to_uppercase().to_string()
+            } else {
+                c.to_lowercase().to_string()
+            };
+            upper = !upper;
+            mocked
+        })
+        .collect()
+}
+
+fn leet(c: char) -> char {
+    match c.to_ascii_lowercase() {
+        'a' => '4',
+        'b' => '8',
+        'e' => '3',
+        'g' => '6',
+        'i' => '1',
+        'o' => '0',
+        's' => '5',
+        't' => '7',
+        'z' => '2',
+        _ => c,
+    }
+}
+
+fn zalgo(text: &str, intensity: u8) -> String {
+    let mut rng = rand::thread_rng();
+    let mut out = String::new();
+    for c in text.chars() {
+        out.push(c);
+        if c.is_whitespace() || intensity == 0 {
+            continue;
+        }
+        for _ in 0..rng.gen_range(1..=intensity) {
+            let mark = rng.gen_range(0x0300..=0x036F);
+            out.extend(char::from_u32(mark));
+        }
+    }
+    out
+}
+
+fn upside_down(c: char) -> char {
+    match c {
+        'a' => 'ɐ',
+        'b' => 'q',
+        'c' => 'ɔ',
+        'd' => 'p',
+        'e' => 'ǝ',
+        'f' => 'ɟ',
+        'g' => 'ƃ',
+        'h' => 'ɥ',
+        'i' => 'ᴉ',
+        'j' => 'ɾ',
+        'k' => 'ʞ',
+        'm' => 'ɯ',
+        'n' => 'u',
+        'p' => 'd',
+        'q' => 'b',
+        'r' => 'ɹ',
+        't' => 'ʇ',
+        'u' => 'n',
+        'v' => 'ʌ',
+        'w' => 'ʍ',
+        'y' => 'ʎ',
+        'A' => '∀',
+        'C' => 'Ɔ',
+        'E' => 'Ǝ',
+        'F' => 'Ⅎ',
+        'G' => '⅁',
+        'J' => 'ſ',
+        'L' => '˥',
+        'M' => 'W',
+        'P' => 'Ԁ',
+        'T' => '⊥',
+        'U' => '∩',
+        'V' => 'Λ',
+        'W' => 'M',
+        'Y' => '⅄',
+        '1' => 'Ɩ',
+        '2' => 'ᄅ',
+        '3' => 'Ɛ',
+        '4' => 'ㄣ',
+        '5' => 'ϛ',
+        '6' => '9',
+        '7' => 'ㄥ',
+        '9' => '6',
+        '.' => '˙',
+        ',' => '\'',
+        '\'' => ',',
+        '"' => '„',
+        '?' => '¿',
+        '!' => '¡',
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '_' => '‾',
+        '&' => '⅋',
+        _ => c,
+    }
+}
+
+fn full_width(c: char) -> char {
+    match c {
+        ' ' => '\u{3000}',
+        '!'..='~' => char::from_u32(c as u32 + 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn small_cap(c: char) -> char {
+    const SMALL_CAPS: [char; 26] = [
+        'ᴀ', 'ʙ', 'ᴄ', 'ᴅ', 'ᴇ', 'ꜰ', 'ɢ', 'ʜ', 'ɪ', 'ᴊ', 'ᴋ', 'ʟ', 'ᴍ', 'ɴ', 'ᴏ', 'ᴘ', 'ǫ', 'ʀ',
+        'ꜱ', 'ᴛ', 'ᴜ', 'ᴠ', 'ᴡ', 'x', 'ʏ', 'ᴢ',
+    ];
+    match c {
+        'a'..='z' => SMALL_CAPS[(c as u8 - b'a') as usize],
+        _ => c,
+    }
+}
+
+/// Letters become 🇦-style indicators, separated by spaces so neighbouring
+/// letters don't merge into flags.
+fn regional_indicators(text: &str) -> String {
+    text.chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            lower @ 'a'..='z' => char::from_u32(0x1F1E6 + (lower as u32 - 'a' as u32))
+                .map_or_else(String::new, String::from),
+            '0'..='9' => format!("{}\u{FE0F}\u{20E3}", c),
+            '!' => String::from("❗"),
+            '?' => String::from("❓"),
+            ' ' => String::from("  "),
+            _ => c.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn owo(text: &str, style: OwoStyle) -> String {
+    let faces: &[&str] = match style {
+        OwoStyle::Owo => &["OwO", "owo", ">w<", "^w^", "(・`ω´・)"],
+        OwoStyle::Uwu => &["UwU", "uwu", "(ᵘﻌᵘ)", "(◕ᴗ◕✿)", "ʕ•ᴥ•ʔ"],
+        OwoStyle::Nya => &["nya~", "nyaa~", "=^..^=", "(=^･ω･^=)", "ฅ^•ﻌ•^ฅ"],
+    };
+    let mut rng = rand::thread_rng();
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    let mut word_start = true;
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        if word_start && style == OwoStyle::Uwu && c.is_alphabetic() && rng.gen_ratio(1, 5) {
+            out.push(c);
+            out.push('-');
+        }
+        word_start = c.is_whitespace();
+        match c {
+            'r' | 'l' => out.push('w'),
+            'R' | 'L' => out.push('W'),
+            'n' | 'N' if matches!(next, Some('a' | 'e' | 'i' | 'o' | 'u')) => {
+                out.push(c);
+                out.push('y');
+            }
+            '.' | '!' | '?' if next.is_none_or(char::is_whitespace) => {
+                out.push(c);
+                if let Some(face) = faces.choose(&mut rng) {
+                    out.push(' ');
+                    out.push_str(face);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    if !text.trim_end().ends_with(['.', '!', '?']) {
+        if let Some(face) = faces.choose(&mut rng) {
+            out.push(' ');
+            out.push_str(face);
+        }
+    }
+    out
+}
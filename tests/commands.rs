@@ -0,0 +1,459 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Commands and gallery buttons driven through the framework, see
+//! `harness/mod.rs` for how.
+
+mod harness;
+
+use harness::{Harness, Sent, AUTHOR, CHANNEL_ID, SOMEONE_ELSE};
+use serde_json::json;
+use std::time::Duration;
+use wiremock::{
+    matchers::{method, path, path_regex},
+    Mock, ResponseTemplate,
+};
+
+const FOX: &str = include_str!("fixtures/randomfox.json");
+const FOX_URL: &str = "https://randomfox.ca/images/42.jpg";
+const OTHER_FOX: &str =
+    r#"{"image":"https://randomfox.ca/images/7.jpg","link":"https://randomfox.ca/?i=7"}"#;
+const OTHER_FOX_URL: &str = "https://randomfox.ca/images/7.jpg";
+
+fn only(mut sent: Vec<Sent>) -> Sent {
+    assert_eq!(sent.len(), 1, "expected one message, got {:#?}", sent);
+    sent.remove(0)
+}
+
+/// Serves `body` for the next `times` fox requests.
+async fn serve_fox(harness: &Harness, body: &str, times: u64) {
+    Mock::given(method("GET"))
+        .and(path("/floof/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/json"))
+        .up_to_n_times(times)
+        .mount(&harness.images)
+        .await;
+}
+
+async fn fail_fox(harness: &Harness) {
+    Mock::given(method("GET"))
+        .and(path("/floof/"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&harness.images)
+        .await;
+}
+
+#[tokio::test]
+async fn ascii_sends_a_code_block() {
+    let harness = Harness::new().await;
+    let reply = only(
+        harness
+            .slash("fun ascii", json!({ "text": "floppa" }))
+            .await,
+    );
+
+    assert_eq!(reply.callback, Some(4));
+    assert!(reply.content().starts_with("```\n"), "{}", reply.content());
+    assert!(reply.content().ends_with("\n```"), "{}", reply.content());
+    assert!(reply.files.is_empty());
+}
+
+#[tokio::test]
+async fn ascii_draws_with_every_font() {
+    let harness = Harness::with_config(|config| config.cooldowns.default.user_secs = 0).await;
+    for font in ["standard", "blocks", "serif", "mini", "hash"] {
+        let reply = only(
+            harness
+                .slash("fun ascii", json!({ "text": "floppa", "font": font }))
+                .await,
+        );
+        assert!(
+            reply.content().starts_with("```\n"),
+            "{}: {}",
+            font,
+            reply.content()
+        );
+    }
+}
+
+#[tokio::test]
+async fn ascii_too_big_for_a_message_is_a_file() {
+    let harness = Harness::new().await;
+    let text = "floppa ".repeat(40);
+    let reply = only(
+        harness
+            .slash("fun ascii", json!({ "text": text, "width": 200 }))
+            .await,
+    );
+
+    assert!(reply.content().contains("as a file"), "{}", reply.content());
+    assert_eq!(reply.files.len(), 1);
+    let (filename, art) = &reply.files[0];
+    assert_eq!(filename, "ascii.txt");
+    assert!(art.len() > 2000);
+}
+
+#[tokio::test]
+async fn ascii_prefix_text_is_never_read_as_options() {
+    let harness = Harness::with_config(|config| config.cooldowns.default.user_secs = 0).await;
+    let number = only(harness.prefix("fl.fun ascii 42").await);
+    let font_name = only(harness.prefix("fl.fun ascii mini golf").await);
+
+    assert!(
+        number.content().starts_with("```\n"),
+        "{}",
+        number.content()
+    );
+    let plain = only(
+        harness
+            .slash("fun ascii", json!({ "text": "mini golf" }))
+            .await,
+    );
+    assert_eq!(font_name.content(), plain.content());
+}
+
+#[tokio::test]
+async fn ascii_prefix_reads_marked_options() {
+    let harness = Harness::with_config(|config| config.cooldowns.default.user_secs = 0).await;
+    let prefix = only(harness.prefix("fl.fun ascii floppa font=mini").await);
+    let slash = only(
+        harness
+            .slash("fun ascii", json!({ "text": "floppa", "font": "mini" }))
+            .await,
+    );
+    assert_eq!(prefix.content(), slash.content());
+
+    let reply = only(harness.prefix("fl.fun ascii floppa width=5").await);
+    assert_eq!(reply.embed()["title"], "Invalid input");
+}
+
+#[tokio::test]
+async fn owo_prefix_reads_a_marked_style() {
+    let harness = Harness::new().await;
+    let reply = only(
+        harness
+            .prefix("fl.fun text owo hello friend style=nya~")
+            .await,
+    );
+
+    assert!(
+        reply.content().starts_with("hewwo fwiend "),
+        "{}",
+        reply.content()
+    );
+    assert!(!reply.content().contains("style"), "{}", reply.content());
+}
+
+#[tokio::test]
+async fn zalgo_prefix_text_is_never_read_as_options() {
+    let harness = Harness::with_config(|config| config.cooldowns.default.user_secs = 0).await;
+    let reply = only(harness.prefix("fl.fun text zalgo 7").await);
+    assert!(reply.content().starts_with('7'), "{}", reply.content());
+
+    let reply = only(
+        harness
+            .prefix("fl.fun text zalgo cursed intensity=11")
+            .await,
+    );
+    assert_eq!(reply.embed()["title"], "Invalid input");
+}
+
+#[tokio::test]
+async fn uwuifier_disables_mentions() {
+    let harness = Harness::new().await;
+    let reply = only(
+        harness
+            .slash("fun uwuifier", json!({ "text": "hello @everyone" }))
+            .await,
+    );
+
+    assert!(reply.content().contains("hewwo"), "{}", reply.content());
+    assert_eq!(reply.message["allowed_mentions"]["parse"], json!([]));
+}
+
+#[tokio::test]
+async fn prefix_commands_reply_in_the_channel() {
+    let harness = Harness::new().await;
+    let reply = only(harness.prefix("fl.fun uwuifier hello there").await);
+
+    assert_eq!(reply.method, "POST");
+    assert_eq!(
+        reply.path,
+        format!("/api/v10/channels/{}/messages", CHANNEL_ID)
+    );
+    assert!(reply.content().contains("hewwo"), "{}", reply.content());
+}
+
+#[tokio::test]
+async fn avatar_defaults_to_the_author() {
+    let harness = Harness::new().await;
+    let reply = only(harness.slash("userinfo avatar", json!({})).await);
+
+    let embed = reply.embed();
+    assert_eq!(embed["title"], "floppa's avatar");
+    assert_eq!(
+        embed["image"]["url"],
+        format!(
+            "https://cdn.discordapp.com/avatars/{}/{}.png?size=1024",
+            AUTHOR.id,
+            AUTHOR.avatar.unwrap()
+        )
+    );
+}
+
+#[tokio::test]
+async fn httpcat_shows_the_status() {
+    let harness = Harness::new().await;
+    let reply = only(
+        harness
+            .slash("animals httpcat", json!({ "code": 404 }))
+            .await,
+    );
+
+    let embed = reply.embed();
+    assert_eq!(embed["title"], "404 Not Found");
+    assert_eq!(embed["image"]["url"], "https://http.cat/404.jpg");
+}
+
+#[tokio::test]
+async fn httpcat_unknown_status_is_an_error() {
+    let harness = Harness::new().await;
+    let reply = only(
+        harness
+            .slash("animals httpcat", json!({ "code": 299 }))
+            .await,
+    );
+
+    assert!(reply.is_ephemeral());
+    assert_eq!(reply.embed()["title"], "Invalid input");
+    assert_eq!(
+        reply.embed()["description"],
+        "299 is not a known HTTP status code."
+    );
+}
+
+#[tokio::test]
+async fn image_command_failure_is_explained() {
+    let harness = Harness::new().await;
+    fail_fox(&harness).await;
+    let reply = only(harness.slash("animals fox", json!({})).await);
+
+    // Image commands defer first, so the error is a follow-up.
+    assert_eq!(reply.callback, None);
+    assert_eq!(reply.embed()["title"], "Image service unavailable");
+}
+
+#[tokio::test]
+async fn gallery_rerolls_and_goes_back() {
+    let harness = Harness::new().await;
+    serve_fox(&harness, FOX, 1).await;
+    serve_fox(&harness, OTHER_FOX, 1).await;
+
+    let first = only(harness.slash("animals fox", json!({})).await);
+    assert_eq!(first.embed()["title"], "Random Fox");
+    assert_eq!(first.embed()["image"]["url"], FOX_URL);
+    assert!(first.button("◀ Previous").disabled);
+    assert!(first.button("Next ▶").disabled);
+
+    // New defers first and then edits the message, since fetching can be slow.
+    let second = only(harness.click(&first, "New Fox").await);
+    assert_eq!(second.method, "PATCH");
+    assert_eq!(second.embed()["image"]["url"], OTHER_FOX_URL);
+    assert_eq!(second.embed()["footer"]["text"], "Image 2/2");
+    assert!(!second.button("◀ Previous").disabled);
+    assert!(second.button("Next ▶").disabled);
+
+    let back = only(harness.click(&second, "◀ Previous").await);
+    assert_eq!(back.callback, Some(7));
+    assert_eq!(back.embed()["image"]["url"], FOX_URL);
+    assert_eq!(back.embed()["footer"]["text"], "Image 1/2");
+    assert!(back.button("◀ Previous").disabled);
+    assert!(!back.button("Next ▶").disabled);
+
+    let forward = only(harness.click(&back, "Next ▶").await);
+    assert_eq!(forward.embed()["image"]["url"], OTHER_FOX_URL);
+}
+
+#[tokio::test]
+async fn failed_reroll_keeps_the_image() {
+    let harness = Harness::new().await;
+    serve_fox(&harness, FOX, 1).await;
+    let first = only(harness.slash("animals fox", json!({})).await);
+
+    fail_fox(&harness).await;
+    let reply = only(harness.click(&first, "New Fox").await);
+    assert!(reply.is_ephemeral());
+    assert_eq!(reply.embed()["title"], "Image service unavailable");
+}
+
+#[tokio::test]
+async fn save_button_adds_to_the_clickers_favourites() {
+    let harness = Harness::new().await;
+    serve_fox(&harness, FOX, 1).await;
+    let first = only(harness.slash("animals fox", json!({})).await);
+
+    let saved = only(harness.click(&first, "⭐ Save").await);
+    assert!(saved.is_ephemeral());
+    assert!(saved.content().starts_with("Saved"), "{}", saved.content());
+
+    let again = only(harness.click(&first, "⭐ Save").await);
+    assert!(again.content().contains("already"), "{}", again.content());
+
+    let theirs = only(harness.click_as(SOMEONE_ELSE, &first, "⭐ Save").await);
+    assert!(
+        theirs.content().starts_with("Saved"),
+        "{}",
+        theirs.content()
+    );
+
+    let favourites = harness.data().storage.favourites();
+    assert_eq!(favourites.count(AUTHOR.id).await.unwrap(), 1);
+    assert_eq!(favourites.count(SOMEONE_ELSE.id).await.unwrap(), 1);
+}
+
+async fn fox_requests(harness: &Harness) -> usize {
+    let requests = harness.images.received_requests().await.unwrap_or_default();
+    requests
+        .iter()
+        .filter(|r| r.url.path() == "/floof/")
+        .count()
+}
+
+#[tokio::test]
+async fn images_the_channel_just_saw_are_fetched_again() {
+    let harness = Harness::new().await;
+    serve_fox(&harness, FOX, 2).await;
+    serve_fox(&harness, OTHER_FOX, 1).await;
+
+    let first = only(harness.slash("animals fox", json!({})).await);
+    assert_eq!(first.embed()["image"]["url"], FOX_URL);
+
+    let second = only(harness.click(&first, "New Fox").await);
+    assert_eq!(second.embed()["image"]["url"], OTHER_FOX_URL);
+    assert_eq!(second.embed()["footer"]["text"], "Image 2/2");
+    assert_eq!(fox_requests(&harness).await, 3);
+}
+
+#[tokio::test]
+async fn repeats_are_shown_once_retries_run_out() {
+    let harness = Harness::new().await;
+    serve_fox(&harness, FOX, 10).await;
+
+    let first = only(harness.slash("animals fox", json!({})).await);
+    let second = only(harness.click(&first, "New Fox").await);
+    assert_eq!(second.embed()["image"]["url"], FOX_URL);
+    // One fetch for the command, then the reroll and three retries.
+    assert_eq!(fox_requests(&harness).await, 5);
+}
+
+#[tokio::test]
+async fn reroll_uses_a_prefetched_image() {
+    let harness = Harness::with_config(|config| {
+        config.prefetch.size = 1;
+        // Every fetch here returns the same image.
+        config.duplicates.window = 0;
+    })
+    .await;
+    let prefetched = format!("{}/images/7.jpg", harness.images.uri());
+    // The command and the refill it starts both fetch, in either order.
+    let fox = json!({ "image": prefetched, "link": "https://randomfox.ca/?i=7" });
+    Mock::given(method("GET"))
+        .and(path("/floof/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fox))
+        .mount(&harness.images)
+        .await;
+    Mock::given(method("HEAD"))
+        .and(path_regex("^/images/"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&harness.images)
+        .await;
+
+    let first = only(harness.slash("animals fox", json!({})).await);
+    for _ in 0..200 {
+        if harness.data().prefetch.buffered("randomfox") == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // Nothing to wait for, so the click is answered with the new image.
+    let second = only(harness.click(&first, "New Fox").await);
+    assert_eq!(second.callback, Some(7));
+    assert_eq!(second.embed()["image"]["url"], prefetched);
+    assert_eq!(second.embed()["footer"]["text"], "Image 2/2");
+}
+
+/// Checks run for every parent command, and twice for slash commands, so
+/// they mustn't be what starts the cooldown.
+#[tokio::test]
+async fn first_use_of_a_nested_command_goes_through() {
+    let harness = Harness::new().await;
+    let slash = only(
+        harness
+            .slash("fun text reverse", json!({ "text": "floppa" }))
+            .await,
+    );
+    assert_eq!(slash.content(), "appolf");
+
+    let prefix = only(harness.prefix("fl.fun uwuifier hello").await);
+    assert!(prefix.content().contains("hewwo"), "{}", prefix.content());
+}
+
+#[tokio::test]
+async fn commands_have_a_cooldown_per_user() {
+    let harness = Harness::new().await;
+    let first = only(
+        harness
+            .slash("fun uwuifier", json!({ "text": "hello" }))
+            .await,
+    );
+    assert!(first.content().contains("hewwo"), "{}", first.content());
+
+    let again = only(
+        harness
+            .slash("fun uwuifier", json!({ "text": "hello" }))
+            .await,
+    );
+    assert!(again.is_ephemeral());
+    assert_eq!(again.embed()["title"], "Slow down");
+    assert_eq!(
+        again.embed()["description"],
+        "Give it a moment and try again in 2s."
+    );
+
+    let theirs = only(
+        harness
+            .slash_as(SOMEONE_ELSE, "fun uwuifier", json!({ "text": "hello" }))
+            .await,
+    );
+    assert!(theirs.content().contains("hewwo"), "{}", theirs.content());
+}
+
+#[tokio::test]
+async fn new_button_has_a_cooldown() {
+    let harness = Harness::new().await;
+    serve_fox(&harness, FOX, 1).await;
+    serve_fox(&harness, OTHER_FOX, 1).await;
+    let first = only(harness.slash("animals fox", json!({})).await);
+    let second = only(harness.click(&first, "New Fox").await);
+
+    let again = only(harness.click(&second, "New Fox").await);
+    assert!(again.is_ephemeral());
+    assert_eq!(again.embed()["title"], "Slow down");
+    assert_eq!(fox_requests(&harness).await, 2);
+
+    // Going back doesn't fetch anything, so it has no cooldown.
+    let back = only(harness.click(&second, "◀ Previous").await);
+    assert_eq!(back.embed()["image"]["url"], FOX_URL);
+}
@@ -0,0 +1,490 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runs commands and button clicks through the real framework without a
+//! Discord connection. Events are handed straight to `poise::dispatch_event`
+//! and serenity's REST client is pointed at a local stand-in for Discord,
+//! which records everything a command sends.
+
+#![allow(dead_code)]
+
+use floppabot::{Config, Data, Error};
+use poise::serenity_prelude as serenity;
+use serde_json::{json, Value};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use wiremock::{matchers::any, Mock, MockServer, Request, Respond, ResponseTemplate};
+
+pub const APPLICATION_ID: u64 = 1_081_000_000_000_000_001;
+/// Bot users share their application's id.
+pub const BOT_ID: u64 = APPLICATION_ID;
+pub const CHANNEL_ID: u64 = 1_081_000_000_000_000_100;
+const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+const EPHEMERAL: u64 = 1 << 6;
+const DEFERRED_MESSAGE: u64 = 5;
+const DEFERRED_UPDATE: u64 = 6;
+
+#[derive(Clone, Copy)]
+pub struct User {
+    pub id: u64,
+    pub name: &'static str,
+    pub avatar: Option<&'static str>,
+}
+
+pub const AUTHOR: User = User {
+    id: 1_081_000_000_000_000_200,
+    name: "floppa",
+    avatar: Some("8342729096ea3675442027381ff50dfe"),
+};
+
+pub const SOMEONE_ELSE: User = User {
+    id: 1_081_000_000_000_000_300,
+    name: "caracal",
+    avatar: None,
+};
+
+fn user_json(user: User) -> Value {
+    json!({
+        "id": user.id.to_string(),
+        "username": user.name,
+        "discriminator": "0001",
+        "avatar": user.avatar,
+    })
+}
+
+/// A message as Discord would return it, built from what was sent.
+fn message_json(id: u64, channel_id: u64, sent: &Value) -> Value {
+    let embeds = sent["embeds"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|mut embed| {
+            embed["type"] = json!("rich");
+            embed
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel_id.to_string(),
+        "author": user_json(User { id: BOT_ID, name: "floppabot", avatar: None }),
+        "content": sent["content"].as_str().unwrap_or_default(),
+        "timestamp": "2023-04-01T12:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": embeds,
+        "components": sent["components"].as_array().cloned().unwrap_or_default(),
+        "pinned": false,
+        "type": 0,
+    })
+}
+
+/// Splits a request body into its JSON payload and attached files. Bodies
+/// with files are multipart, with the JSON in a `payload_json` part.
+fn parse_body(body: &[u8]) -> (Value, Vec<(String, Vec<u8>)>) {
+    if !body.starts_with(b"--") {
+        return (
+            serde_json::from_slice(body).unwrap_or(Value::Null),
+            Vec::new(),
+        );
+    }
+    let body = String::from_utf8_lossy(body);
+    let boundary = body.lines().next().unwrap_or_default().to_string();
+    let mut payload = Value::Null;
+    let mut files = Vec::new();
+    for part in body.split(boundary.as_str()) {
+        let (headers, content) = match part.split_once("\r\n\r\n") {
+            Some(split) => split,
+            None => continue,
+        };
+        let content = content.strip_suffix("\r\n").unwrap_or(content);
+        if let Some((_, rest)) = headers.split_once("filename=\"") {
+            let filename = rest.split('"').next().unwrap_or_default();
+            files.push((filename.to_string(), content.as_bytes().to_vec()));
+        } else if headers.contains("name=\"payload_json\"") {
+            payload = serde_json::from_str(content).unwrap_or(Value::Null);
+        }
+    }
+    (payload, files)
+}
+
+/// Answers every request the bot makes to Discord with the least it needs:
+/// 204 for interaction callbacks and deletes, and otherwise the message that
+/// was sent or edited echoed back.
+#[derive(Default)]
+struct FakeDiscord {
+    next_message: AtomicU64,
+}
+
+impl Respond for FakeDiscord {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let path = request.url.path();
+        let method = request.method.to_string();
+        if path.ends_with("/callback") || method == "DELETE" {
+            return ResponseTemplate::new(204);
+        }
+        if path.ends_with("/gateway") {
+            return ResponseTemplate::new(200)
+                .set_body_json(json!({ "url": "wss://gateway.discord.invalid" }));
+        }
+        let segments = path.split('/').collect::<Vec<_>>();
+        if let ["", "api", _, "users", id] = segments.as_slice() {
+            let user = [AUTHOR, SOMEONE_ELSE]
+                .into_iter()
+                .find(|user| user.id.to_string() == *id)
+                .unwrap_or(SOMEONE_ELSE);
+            return ResponseTemplate::new(200).set_body_json(user_json(user));
+        }
+
+        let channel_id = match segments.as_slice() {
+            ["", "api", _, "channels", id, ..] => id.parse().unwrap_or(CHANNEL_ID),
+            _ => CHANNEL_ID,
+        };
+        let (payload, _) = parse_body(&request.body);
+        let id = self.next_message.fetch_add(1, Ordering::Relaxed) + 1;
+        ResponseTemplate::new(200).set_body_json(message_json(id, channel_id, &payload))
+    }
+}
+
+/// A request the bot made to Discord that sends or changes a message.
+#[derive(Clone, Debug)]
+pub struct Sent {
+    pub method: String,
+    pub path: String,
+    /// The interaction response type, for responses to an interaction.
+    pub callback: Option<u64>,
+    /// The message part of the request: content, embeds, components and flags.
+    pub message: Value,
+    pub files: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub struct Button {
+    pub label: String,
+    pub custom_id: String,
+    pub disabled: bool,
+}
+
+impl Sent {
+    /// `None` for requests that don't carry a message, like fetches and
+    /// deferred responses.
+    fn from_request(request: &Request) -> Option<Self> {
+        let method = request.method.to_string();
+        if method == "GET" || method == "DELETE" {
+            return None;
+        }
+        let path = request.url.path().to_string();
+        let (payload, files) = parse_body(&request.body);
+        let (callback, message) = if path.ends_with("/callback") {
+            let kind = payload["type"].as_u64()?;
+            if kind == DEFERRED_MESSAGE || kind == DEFERRED_UPDATE {
+                return None;
+            }
+            (
+                Some(kind),
+                payload.get("data").cloned().unwrap_or(json!({})),
+            )
+        } else {
+            (None, payload)
+        };
+        if !message.is_object() {
+            return None;
+        }
+        Some(Self {
+            method,
+            path,
+            callback,
+            message,
+            files,
+        })
+    }
+
+    pub fn content(&self) -> &str {
+        self.message["content"].as_str().unwrap_or_default()
+    }
+
+    pub fn embed(&self) -> &Value {
+        self.message["embeds"]
+            .get(0)
+            .unwrap_or_else(|| panic!("no embed in {}", self.message))
+    }
+
+    pub fn is_ephemeral(&self) -> bool {
+        self.message["flags"].as_u64().unwrap_or_default() & EPHEMERAL != 0
+    }
+
+    pub fn buttons(&self) -> Vec<Button> {
+        self.message["components"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|row| row["components"].as_array().cloned().unwrap_or_default())
+            .filter(|component| component["type"] == 2)
+            .map(|button| Button {
+                label: button["label"].as_str().unwrap_or_default().to_string(),
+                custom_id: button["custom_id"].as_str().unwrap_or_default().to_string(),
+                disabled: button["disabled"].as_bool().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    pub fn button(&self, label: &str) -> Button {
+        self.buttons()
+            .into_iter()
+            .find(|button| button.label == label)
+            .unwrap_or_else(|| panic!("no {:?} button in {}", label, self.message))
+    }
+}
+
+/// Points every image provider at `base`. TheCatAPI and TheDogAPI share
+/// their paths, so they get a prefix each to tell them apart.
+pub fn mock_endpoints(config: &mut Config, base: &str) {
+    let endpoints = &mut config.endpoints;
+    endpoints.thecatapi = format!("{}/cat/v1", base);
+    endpoints.thedogapi = format!("{}/dog/v1", base);
+    endpoints.randomfox = base.to_string();
+    endpoints.shibe = base.to_string();
+    endpoints.awscat = base.to_string();
+    endpoints.randomdog = base.to_string();
+    endpoints.randomduck = base.to_string();
+    endpoints.nekosbest = base.to_string();
+    endpoints.waifupics = base.to_string();
+    endpoints.purrbot = base.to_string();
+    config.api_keys.cat = String::from("cat-key");
+    config.api_keys.dog = String::from("dog-key");
+}
+
+/// The framework sets these when it starts, `usage` records them.
+fn set_qualified_names(commands: &mut [poise::Command<Data, Error>], parent: Option<&str>) {
+    for command in commands {
+        if let Some(parent) = parent {
+            command.qualified_name = format!("{} {}", parent, command.name);
+        }
+        let name = command.qualified_name.clone();
+        set_qualified_names(&mut command.subcommands, Some(&name));
+    }
+}
+
+/// Builds `/a b c` as nested subcommand options around `options`.
+fn command_data(command: &str, options: Value) -> Value {
+    let mut words = command.split_whitespace().collect::<Vec<_>>();
+    let root = words.remove(0);
+    let mut options = options
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| {
+            let kind = match value {
+                Value::Bool(_) => 5,
+                Value::Number(number) if number.is_f64() => 10,
+                Value::Number(_) => 4,
+                _ => 3,
+            };
+            json!({ "name": name, "type": kind, "value": value })
+        })
+        .collect::<Vec<_>>();
+    for (depth, word) in words.iter().enumerate().rev() {
+        // The innermost word is a subcommand, any above it are groups.
+        let kind = if depth + 1 == words.len() { 1 } else { 2 };
+        options = vec![json!({ "name": word, "type": kind, "options": options })];
+    }
+    json!({ "id": "1", "name": root, "type": 1, "options": options })
+}
+
+pub struct Harness {
+    /// The stand-in for Discord's REST API.
+    pub discord: MockServer,
+    /// Every image provider's endpoint points here, mount responses on it.
+    pub images: MockServer,
+    client: serenity::Client,
+    ctx: serenity::Context,
+    options: poise::FrameworkOptions<Data, Error>,
+    data: Data,
+    next_id: AtomicU64,
+}
+
+impl Harness {
+    pub async fn new() -> Self {
+        Self::with_config(|_| {}).await
+    }
+
+    /// Like `new`, with `configure` getting the last word on the config.
+    /// Prefetching is off unless `configure` turns it on, so tests only see
+    /// the requests they cause.
+    pub async fn with_config(configure: impl FnOnce(&mut Config)) -> Self {
+        let discord = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(FakeDiscord::default())
+            .mount(&discord)
+            .await;
+        let images = MockServer::start().await;
+
+        let mut config = Config {
+            database_url: String::from("sqlite::memory:"),
+            ..Config::default()
+        };
+        config.prefetch.size = 0;
+        mock_endpoints(&mut config, &images.uri());
+        configure(&mut config);
+
+        let http = serenity::HttpBuilder::new("harness-token")
+            .proxy(discord.uri())
+            .expect("mock server URI is a valid proxy")
+            .ratelimiter_disabled(true)
+            .application_id(APPLICATION_ID)
+            .build();
+        let client = serenity::ClientBuilder::new_with_http(
+            http,
+            serenity::GatewayIntents::non_privileged(),
+        )
+        .await
+        .expect("client builds against the fake gateway endpoint");
+        let (shard_tx, _) = serenity::futures::channel::mpsc::unbounded();
+        let ctx = serenity::Context {
+            data: client.data.clone(),
+            shard: serenity::ShardMessenger::new(shard_tx),
+            shard_id: 0,
+            http: client.cache_and_http.http.clone(),
+            cache: client.cache_and_http.cache.clone(),
+        };
+
+        let mut options = floppabot::options(&config).expect("all commands are enabled");
+        set_qualified_names(&mut options.commands, None);
+        let data = floppabot::data(config, ctx.http.clone())
+            .await
+            .expect("in-memory database opens");
+
+        Self {
+            discord,
+            images,
+            client,
+            ctx,
+            options,
+            data,
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    pub fn data(&self) -> &Data {
+        &self.data
+    }
+
+    /// A fresh snowflake from the current time, so anything that checks how
+    /// old an id is sees it as brand new.
+    fn snowflake(&self) -> u64 {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let increment = self.next_id.fetch_add(1, Ordering::Relaxed) & 0xFFF;
+        ((now_ms - DISCORD_EPOCH_MS) << 22) | increment
+    }
+
+    /// Dispatches `event` and returns what the bot sent while handling it.
+    async fn dispatch(&self, event: poise::Event<'_>) -> Vec<Sent> {
+        let seen = self.requests().await.len();
+        let framework = poise::FrameworkContext {
+            bot_id: serenity::UserId(BOT_ID),
+            options: &self.options,
+            user_data: &self.data,
+            shard_manager: &self.client.shard_manager,
+        };
+        poise::dispatch_event(framework, &self.ctx, &event).await;
+        self.requests()
+            .await
+            .iter()
+            .skip(seen)
+            .filter_map(Sent::from_request)
+            .collect()
+    }
+
+    async fn requests(&self) -> Vec<Request> {
+        self.discord.received_requests().await.unwrap_or_default()
+    }
+
+    fn interaction(&self, user: User, kind: u8, data: Value) -> Value {
+        let id = self.snowflake();
+        json!({
+            "id": id.to_string(),
+            "application_id": APPLICATION_ID.to_string(),
+            "type": kind,
+            "data": data,
+            "channel_id": CHANNEL_ID.to_string(),
+            "user": user_json(user),
+            "token": format!("token-{}", id),
+            "version": 1,
+            "locale": "en-GB",
+        })
+    }
+
+    /// Runs a slash command in DMs, e.g.
+    /// `slash("fun ascii", json!({ "text": "floppa" }))`.
+    pub async fn slash(&self, command: &str, options: Value) -> Vec<Sent> {
+        self.slash_as(AUTHOR, command, options).await
+    }
+
+    pub async fn slash_as(&self, user: User, command: &str, options: Value) -> Vec<Sent> {
+        let interaction = self.interaction(user, 2, command_data(command, options));
+        let interaction = serde_json::from_value(interaction).expect("valid command interaction");
+        self.dispatch(poise::Event::InteractionCreate { interaction })
+            .await
+    }
+
+    /// Sends `content` as a DM to the bot, e.g. `prefix("fl.fun uwuifier hi")`.
+    pub async fn prefix(&self, content: &str) -> Vec<Sent> {
+        let message = json!({
+            "id": self.snowflake().to_string(),
+            "channel_id": CHANNEL_ID.to_string(),
+            "author": user_json(AUTHOR),
+            "content": content,
+            "timestamp": "2023-04-01T12:00:00.000000+00:00",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0,
+        });
+        let new_message = serde_json::from_value(message).expect("valid message");
+        self.dispatch(poise::Event::Message { new_message }).await
+    }
+
+    /// Clicks the button labelled `label` on a message the bot sent.
+    pub async fn click(&self, message: &Sent, label: &str) -> Vec<Sent> {
+        self.click_as(AUTHOR, message, label).await
+    }
+
+    pub async fn click_as(&self, user: User, message: &Sent, label: &str) -> Vec<Sent> {
+        let button = message.button(label);
+        assert!(!button.disabled, "{:?} is disabled", label);
+        let mut interaction = self.interaction(
+            user,
+            3,
+            json!({ "custom_id": button.custom_id, "component_type": 2 }),
+        );
+        interaction["message"] = message_json(self.snowflake(), CHANNEL_ID, &message.message);
+        let interaction = serde_json::from_value(interaction).expect("valid component interaction");
+        self.dispatch(poise::Event::InteractionCreate { interaction })
+            .await
+    }
+}
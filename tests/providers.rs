@@ -0,0 +1,653 @@
+// Copyright 2023 Meta4245
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Every provider against a local stand-in server serving recorded responses
+//! from `tests/fixtures`, so none of this touches the network.
+
+mod harness;
+
+use floppabot::{
+    error::BotError,
+    http::{self, HttpOptions},
+    providers::{self, ImageResult, Prefetch, ProviderRegistry},
+    Config, Error,
+};
+use std::{sync::Arc, time::Duration};
+use wiremock::{
+    http::Method,
+    matchers::{header, method, path, path_regex, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// (registry name, path it requests, fixture it gets back)
+const PROVIDERS: &[(&str, &str, &str)] = &[
+    (
+        "thecatapi",
+        "/cat/v1/images/search",
+        include_str!("fixtures/thecatapi_search.json"),
+    ),
+    (
+        "thedogapi",
+        "/dog/v1/images/search",
+        include_str!("fixtures/thedogapi_search.json"),
+    ),
+    (
+        "randomfox",
+        "/floof/",
+        include_str!("fixtures/randomfox.json"),
+    ),
+    (
+        "shibe-shibes",
+        "/api/shibes",
+        include_str!("fixtures/shibe.json"),
+    ),
+    (
+        "shibe-birds",
+        "/api/birds",
+        include_str!("fixtures/shibe.json"),
+    ),
+    (
+        "shibe-cats",
+        "/api/cats",
+        include_str!("fixtures/shibe.json"),
+    ),
+    ("awscat", "/meow", include_str!("fixtures/awscat.json")),
+    (
+        "randomdog",
+        "/woof.json",
+        include_str!("fixtures/randomdog.json"),
+    ),
+    (
+        "randomduck",
+        "/api/v2/random",
+        include_str!("fixtures/randomduck.json"),
+    ),
+    (
+        "nekosbest-neko",
+        "/api/v2/neko",
+        include_str!("fixtures/nekosbest.json"),
+    ),
+    (
+        "waifupics-neko",
+        "/sfw/neko",
+        include_str!("fixtures/waifupics.json"),
+    ),
+    (
+        "purrbot-neko",
+        "/api/img/sfw/neko/img",
+        include_str!("fixtures/purrbot.json"),
+    ),
+    (
+        "purrbot-okami",
+        "/api/img/sfw/okami/img",
+        include_str!("fixtures/purrbot.json"),
+    ),
+    (
+        "purrbot-kitsune",
+        "/api/img/sfw/kitsune/img",
+        include_str!("fixtures/purrbot.json"),
+    ),
+];
+
+fn registry(server: &MockServer) -> ProviderRegistry {
+    registry_with(server, "")
+}
+
+/// A registry built from `toml` on top of the defaults.
+fn registry_with(server: &MockServer, toml: &str) -> ProviderRegistry {
+    let mut config = Config::from_toml(toml).unwrap();
+    harness::mock_endpoints(&mut config, &server.uri());
+    providers::default_registry(&config)
+}
+
+fn client(timeout: Duration) -> reqwest::Client {
+    http::client(&HttpOptions {
+        timeout,
+        ..HttpOptions::default()
+    })
+    .unwrap()
+}
+
+async fn fetch(server: &MockServer, name: &str) -> Result<ImageResult, Error> {
+    let provider = registry(server)
+        .get(name)
+        .unwrap_or_else(|| panic!("{} isn't registered", name));
+    provider.fetch(&client(Duration::from_secs(5))).await
+}
+
+fn json(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body, "application/json")
+}
+
+async fn serve(server: &MockServer, at: &str, response: ResponseTemplate) {
+    Mock::given(method("GET"))
+        .and(path(at))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+fn bot_error(name: &str, result: Result<ImageResult, Error>) -> BotError {
+    let err = match result {
+        Ok(image) => panic!("{} succeeded with {}", name, image.url),
+        Err(err) => err,
+    };
+    match err.downcast::<BotError>() {
+        Ok(err) => *err,
+        Err(err) => panic!("{} failed with a non-BotError: {}", name, err),
+    }
+}
+
+fn field<'a>(image: &'a ImageResult, name: &str) -> Option<&'a str> {
+    image
+        .fields
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value.as_str())
+}
+
+#[tokio::test]
+async fn every_provider_parses_its_fixture() {
+    for (name, at, fixture) in PROVIDERS {
+        let server = MockServer::start().await;
+        serve(&server, at, json(fixture)).await;
+
+        let image = fetch(&server, name)
+            .await
+            .unwrap_or_else(|err| panic!("{} failed: {}", name, err));
+        assert!(image.url.starts_with("https://"), "{}: {}", name, image.url);
+    }
+}
+
+#[tokio::test]
+async fn thecatapi_sends_key_and_shows_breed() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cat/v1/images/search"))
+        .and(header("x-api-key", "cat-key"))
+        .respond_with(json(include_str!("fixtures/thecatapi_search.json")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let image = fetch(&server, "thecatapi").await.unwrap();
+    assert_eq!(image.url, "https://cdn2.thecatapi.com/images/O3btzLlsO.png");
+    assert_eq!(field(&image, "Breed"), Some("Bengal"));
+    assert_eq!(field(&image, "Origin"), Some("United States"));
+    assert_eq!(field(&image, "Lifespan"), Some("12 - 15 years"));
+    assert_eq!(field(&image, "Weight"), Some("3 - 7 kg"));
+}
+
+#[tokio::test]
+async fn thedogapi_shows_breed_with_numeric_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/dog/v1/images/search"))
+        .and(header("x-api-key", "dog-key"))
+        .respond_with(json(include_str!("fixtures/thedogapi_search.json")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let image = fetch(&server, "thedogapi").await.unwrap();
+    assert_eq!(image.url, "https://cdn2.thedogapi.com/images/BJa4kxc4X.jpg");
+    assert_eq!(field(&image, "Breed"), Some("Affenpinscher"));
+    assert_eq!(field(&image, "Lifespan"), Some("10 - 12 years"));
+    assert_eq!(field(&image, "Weight"), Some("3 - 6 kg"));
+}
+
+#[tokio::test]
+async fn breed_search_passes_breed_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cat/v1/images/search"))
+        .and(query_param("breed_ids", "beng"))
+        .respond_with(json(include_str!("fixtures/thecatapi_search.json")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let image = fetch(&server, "thecatapi:beng").await.unwrap();
+    assert_eq!(field(&image, "Breed"), Some("Bengal"));
+}
+
+#[tokio::test]
+async fn breeds_are_fetched_once() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cat/v1/breeds"))
+        .respond_with(json(include_str!("fixtures/thecatapi_breeds.json")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = registry(&server).get("thecatapi").unwrap();
+    let http = client(Duration::from_secs(5));
+    let breeds = provider.breeds(&http).await.unwrap();
+    assert_eq!(
+        breeds,
+        vec![
+            (String::from("Abyssinian"), String::from("abys")),
+            (String::from("Bengal"), String::from("beng")),
+        ]
+    );
+    assert_eq!(provider.breeds(&http).await.unwrap(), breeds);
+}
+
+#[tokio::test]
+async fn dog_breeds_have_numeric_ids() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/dog/v1/breeds",
+        json(include_str!("fixtures/thedogapi_breeds.json")),
+    )
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/dog/v1/images/search"))
+        .and(query_param("breed_ids", "23"))
+        .respond_with(json(include_str!("fixtures/thedogapi_search.json")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let registry = registry(&server);
+    let http = client(Duration::from_secs(5));
+    let breeds = registry
+        .get("thedogapi")
+        .unwrap()
+        .breeds(&http)
+        .await
+        .unwrap();
+    assert_eq!(
+        breeds,
+        vec![
+            (String::from("Affenpinscher"), String::from("1")),
+            (String::from("Beagle"), String::from("23")),
+        ]
+    );
+    registry
+        .get("thedogapi:23")
+        .unwrap()
+        .fetch(&http)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn provider_specific_fields() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/api/v2/neko",
+        json(include_str!("fixtures/nekosbest.json")),
+    )
+    .await;
+    serve(
+        &server,
+        "/api/img/sfw/neko/img",
+        json(include_str!("fixtures/purrbot.json")),
+    )
+    .await;
+
+    let neko = fetch(&server, "nekosbest-neko").await.unwrap();
+    assert_eq!(neko.url, "https://nekos.best/api/v2/neko/0042.png");
+    assert_eq!(field(&neko, "Artist"), Some("Rin"));
+
+    let purr = fetch(&server, "purrbot-neko").await.unwrap();
+    assert_eq!(
+        purr.url,
+        "https://purrbot.site/img/sfw/neko/img/neko_042.jpg"
+    );
+    assert_eq!(field(&purr, "API Response Time"), Some("12"));
+}
+
+#[tokio::test]
+async fn server_errors_are_upstream() {
+    for (name, at, _) in PROVIDERS {
+        let server = MockServer::start().await;
+        serve(&server, at, ResponseTemplate::new(500)).await;
+
+        match bot_error(name, fetch(&server, name).await) {
+            BotError::Upstream { status, .. } => assert_eq!(status, Some(500), "{}", name),
+            err => panic!("{}: expected an upstream error, got {}", name, err),
+        }
+    }
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_decode_error() {
+    for (name, at, fixture) in PROVIDERS {
+        let server = MockServer::start().await;
+        let truncated = &fixture[..fixture.len() / 2];
+        serve(&server, at, json(truncated)).await;
+
+        match bot_error(name, fetch(&server, name).await) {
+            BotError::Decode { .. } => {}
+            err => panic!("{}: expected a decode error, got {}", name, err),
+        }
+    }
+}
+
+#[tokio::test]
+async fn wrong_shape_is_a_decode_error() {
+    for (name, at, _) in PROVIDERS {
+        let server = MockServer::start().await;
+        serve(&server, at, json(r#"{"unexpected": true}"#)).await;
+
+        match bot_error(name, fetch(&server, name).await) {
+            BotError::Decode { .. } => {}
+            err => panic!("{}: expected a decode error, got {}", name, err),
+        }
+    }
+}
+
+#[tokio::test]
+async fn empty_results_are_a_decode_error() {
+    let server = MockServer::start().await;
+    serve(&server, "/cat/v1/images/search", json("[]")).await;
+    serve(&server, "/api/shibes", json("[]")).await;
+    serve(&server, "/api/v2/neko", json(r#"{"results": []}"#)).await;
+
+    for name in ["thecatapi", "shibe-shibes", "nekosbest-neko"] {
+        match bot_error(name, fetch(&server, name).await) {
+            BotError::Decode { message, .. } => {
+                assert_eq!(message, "response contained no images", "{}", name)
+            }
+            err => panic!("{}: expected a decode error, got {}", name, err),
+        }
+    }
+}
+
+#[tokio::test]
+async fn slow_responses_time_out() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/floof/",
+        json(include_str!("fixtures/randomfox.json")).set_delay(Duration::from_secs(5)),
+    )
+    .await;
+
+    let provider = registry(&server).get("randomfox").unwrap();
+    let result = provider.fetch(&client(Duration::from_millis(200))).await;
+    match bot_error("randomfox", result) {
+        BotError::Timeout { .. } => {}
+        err => panic!("expected a timeout, got {}", err),
+    }
+}
+
+#[tokio::test]
+async fn unreachable_server_is_upstream_without_status() {
+    // A dropped MockServer goes back to wiremock's pool and keeps answering,
+    // a port nobody listens on anymore doesn't.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let mut config = Config::default();
+    harness::mock_endpoints(&mut config, &closed);
+    let registry = providers::default_registry(&config);
+
+    let result = registry
+        .get("randomduck")
+        .unwrap()
+        .fetch(&client(Duration::from_secs(5)))
+        .await;
+    match bot_error("randomduck", result) {
+        BotError::Upstream { status, .. } => assert_eq!(status, None),
+        err => panic!("expected an upstream error, got {}", err),
+    }
+}
+
+#[tokio::test]
+async fn failover_falls_through_to_next_provider() {
+    let server = MockServer::start().await;
+    serve(&server, "/cat/v1/images/search", ResponseTemplate::new(503)).await;
+    serve(&server, "/meow", json("not json")).await;
+    serve(
+        &server,
+        "/api/cats",
+        json(include_str!("fixtures/shibe.json")),
+    )
+    .await;
+
+    let image = fetch(&server, "cat").await.unwrap();
+    assert!(image.url.starts_with("https://cdn.shibe.online/"));
+}
+
+#[tokio::test]
+async fn failover_reports_the_last_error() {
+    let server = MockServer::start().await;
+    serve(&server, "/dog/v1/images/search", ResponseTemplate::new(500)).await;
+    serve(&server, "/woof.json", ResponseTemplate::new(502)).await;
+
+    match bot_error("dog", fetch(&server, "dog").await) {
+        BotError::Upstream { status, .. } => assert_eq!(status, Some(502)),
+        err => panic!("expected an upstream error, got {}", err),
+    }
+}
+
+const ONE_CAT_A_MINUTE: &str = r#"
+[rate_limits]
+thecatapi = { per_minute = 1, burst = 1 }
+"#;
+
+#[test]
+fn rate_limits_go_over_the_defaults() {
+    let config = Config::from_toml(ONE_CAT_A_MINUTE).unwrap();
+    assert_eq!(config.rate_limits["thecatapi"].per_minute, 1);
+    assert_eq!(config.rate_limits["thedogapi"].per_minute, 30);
+}
+
+#[test]
+fn rate_limits_for_unknown_apis_are_rejected() {
+    let mut config = Config::from_toml(
+        r#"
+discord_token = "token"
+
+[rate_limits]
+thecatapii = { per_minute = 1, burst = 1 }
+"#,
+    )
+    .unwrap();
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("thecatapii"), "{}", err);
+}
+
+#[tokio::test]
+async fn rate_limit_is_shared_with_breed_searches() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/cat/v1/images/search",
+        json(include_str!("fixtures/thecatapi_search.json")),
+    )
+    .await;
+    let registry = registry_with(&server, ONE_CAT_A_MINUTE);
+
+    let http = client(Duration::from_secs(5));
+    registry
+        .get("thecatapi")
+        .unwrap()
+        .fetch(&http)
+        .await
+        .unwrap();
+    let result = registry.get("thecatapi:beng").unwrap().fetch(&http).await;
+    match bot_error("thecatapi:beng", result) {
+        BotError::RateLimited(retry_after) => {
+            assert!(retry_after > Duration::from_secs(50), "{:?}", retry_after)
+        }
+        err => panic!("expected a rate limit, got {}", err),
+    }
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn failover_skips_a_rate_limited_provider() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/cat/v1/images/search",
+        json(include_str!("fixtures/thecatapi_search.json")),
+    )
+    .await;
+    serve(&server, "/meow", ResponseTemplate::new(503)).await;
+    serve(
+        &server,
+        "/api/cats",
+        json(include_str!("fixtures/shibe.json")),
+    )
+    .await;
+    let cat = registry_with(&server, ONE_CAT_A_MINUTE).get("cat").unwrap();
+
+    let http = client(Duration::from_secs(5));
+    let first = cat.fetch(&http).await.unwrap();
+    assert_eq!(first.url, "https://cdn2.thecatapi.com/images/O3btzLlsO.png");
+    let second = cat.fetch(&http).await.unwrap();
+    assert!(second.url.starts_with("https://cdn.shibe.online/"));
+}
+
+/// A fox whose image is served by `server`, so checking it resolves stays
+/// local too.
+async fn serve_local_fox(server: &MockServer, head_status: u16) {
+    let body = format!(
+        r#"{{"image":"{}/images/42.jpg","link":"https://randomfox.ca/?i=42"}}"#,
+        server.uri()
+    );
+    serve(server, "/floof/", json(&body)).await;
+    Mock::given(method("HEAD"))
+        .and(path_regex("^/images/"))
+        .respond_with(ResponseTemplate::new(head_status))
+        .mount(server)
+        .await;
+}
+
+fn prefetch(size: usize, ttl: Duration) -> Arc<Prefetch> {
+    Arc::new(Prefetch::new(client(Duration::from_secs(5)), size, ttl))
+}
+
+/// Refills run in the background, so wait for `count` HEAD requests.
+async fn wait_for_heads(server: &MockServer, count: usize) {
+    for _ in 0..200 {
+        let requests = server.received_requests().await.unwrap_or_default();
+        if requests.iter().filter(|r| r.method == Method::Head).count() >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("expected {} HEAD requests", count);
+}
+
+async fn wait_for_buffered(prefetch: &Prefetch, name: &str, count: usize) {
+    for _ in 0..200 {
+        if prefetch.buffered(name) >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("expected {} prefetched images from {}", count, name);
+}
+
+#[tokio::test]
+async fn prefetch_fills_the_buffer_in_the_background() {
+    let server = MockServer::start().await;
+    serve_local_fox(&server, 200).await;
+    let provider = registry(&server).get("randomfox").unwrap();
+    let prefetch = prefetch(2, Duration::from_secs(600));
+
+    assert!(prefetch.take(&provider).is_none());
+    wait_for_buffered(&prefetch, "randomfox", 2).await;
+    let image = prefetch.take(&provider).unwrap();
+    assert_eq!(image.url, format!("{}/images/42.jpg", server.uri()));
+}
+
+#[tokio::test]
+async fn prefetch_drops_links_that_dont_resolve() {
+    let server = MockServer::start().await;
+    serve_local_fox(&server, 404).await;
+    let provider = registry(&server).get("randomfox").unwrap();
+    let prefetch = prefetch(2, Duration::from_secs(600));
+
+    assert!(prefetch.take(&provider).is_none());
+    // The buffer size plus a few extra attempts, then it gives up.
+    wait_for_heads(&server, 5).await;
+    assert_eq!(prefetch.buffered("randomfox"), 0);
+}
+
+#[tokio::test]
+async fn prefetched_images_expire() {
+    let server = MockServer::start().await;
+    serve_local_fox(&server, 200).await;
+    let provider = registry(&server).get("randomfox").unwrap();
+    let prefetch = prefetch(1, Duration::from_millis(200));
+
+    assert!(prefetch.take(&provider).is_none());
+    wait_for_buffered(&prefetch, "randomfox", 1).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(prefetch.buffered("randomfox"), 0);
+}
+
+#[tokio::test]
+async fn prefetch_of_size_zero_fetches_directly() {
+    let server = MockServer::start().await;
+    serve_local_fox(&server, 200).await;
+    let provider = registry(&server).get("randomfox").unwrap();
+    let prefetch = prefetch(0, Duration::from_secs(600));
+
+    assert!(prefetch.take(&provider).is_none());
+    prefetch.fetch(&provider).await.unwrap();
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 1);
+}
+
+const FOUR_FOXES_A_MINUTE: &str = r#"
+[rate_limits]
+randomfox = { per_minute = 1, burst = 4 }
+"#;
+
+#[tokio::test]
+async fn prefetch_leaves_rate_limit_tokens_for_commands() {
+    let server = MockServer::start().await;
+    serve_local_fox(&server, 200).await;
+    let provider = registry_with(&server, FOUR_FOXES_A_MINUTE)
+        .get("randomfox")
+        .unwrap();
+    let prefetch = prefetch(3, Duration::from_secs(600));
+
+    assert!(prefetch.take(&provider).is_none());
+    // Half the burst stays with commands, so only two of three get fetched.
+    wait_for_buffered(&prefetch, "randomfox", 2).await;
+    let http = client(Duration::from_secs(5));
+    provider.fetch(&http).await.unwrap();
+    provider.fetch(&http).await.unwrap();
+    assert_eq!(prefetch.buffered("randomfox"), 2);
+}
+
+#[tokio::test]
+async fn breed_searches_arent_prefetched() {
+    let server = MockServer::start().await;
+    serve(
+        &server,
+        "/cat/v1/images/search",
+        json(include_str!("fixtures/thecatapi_search.json")),
+    )
+    .await;
+    let provider = registry(&server).get("thecatapi:beng").unwrap();
+    let prefetch = prefetch(2, Duration::from_secs(600));
+
+    assert!(prefetch.take(&provider).is_none());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(prefetch.buffered("thecatapi:beng"), 0);
+    assert!(server.received_requests().await.unwrap().is_empty());
+}